@@ -1,28 +1,118 @@
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::prelude::*;
-use std::collections::HashSet;
+
+mod rules;
+mod trails;
+use rules::*;
+use trails::*;
 
 const INPUT: &str = "input.txt";
-const ROWS: usize = 50;
-const COLS: usize = 50;
-const ROWS_SIGNED: isize = 50;
-const COLS_SIGNED: isize = 50;
-const DIRS: [(isize, isize); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
-
-type Arr = [[usize; COLS]; ROWS];
-type Visited = [[bool; COLS]; ROWS];
-type Cache = [[Option<usize>; COLS]; ROWS];
-
-fn in_bounds(r: isize, c: isize) -> bool {
-    r >= 0 && c >= 0 && r < ROWS_SIGNED && c < COLS_SIGNED
+// Off the map or marked '.', no trail goes through it
+const IMPASSABLE: usize = usize::MAX;
+
+type Pos = (usize, usize);
+type Cache = Vec<Vec<Option<usize>>>;
+
+struct Topo {
+    heights: Vec<Vec<usize>>,
+    rows: usize,
+    cols: usize,
+}
+
+impl Topo {
+    // Rows of single digits as in the puzzle, or of whitespace separated heights
+    fn new(contents: &str) -> Topo {
+        let parse = |val: &str| match val {
+            "." => IMPASSABLE,
+            _ => val.parse().expect("Failed to parse"),
+        };
+
+        let mut heights: Vec<Vec<usize>> = Vec::new();
+
+        for row in contents.split("\n").filter(|l| !l.trim().is_empty()) {
+            if row.trim().contains(char::is_whitespace) {
+                heights.push(row.split_whitespace().map(parse).collect());
+            } else {
+                heights.push(
+                    row.trim()
+                        .chars()
+                        .map(|ch| parse(&ch.to_string()))
+                        .collect(),
+                );
+            }
+        }
+
+        let rows = heights.len();
+        let cols = heights.iter().map(Vec::len).max().unwrap_or_default();
+
+        for row in heights.iter_mut() {
+            row.resize(cols, IMPASSABLE);
+        }
+
+        Topo {
+            heights,
+            rows,
+            cols,
+        }
+    }
+
+    fn height(&self, (r, c): Pos) -> usize {
+        self.heights[r][c]
+    }
+
+    fn is_peak(&self, pos: Pos, rule: &Rule) -> bool {
+        self.height(pos) != IMPASSABLE && rule.is_peak(self.height(pos))
+    }
+
+    // Neighbours the rule lets a hiking trail step to
+    fn climbs(&self, (r, c): Pos, rule: &Rule) -> Vec<Pos> {
+        let value = self.height((r, c));
+        let mut steps: Vec<Pos> = Vec::new();
+
+        for &(dr, dc) in rule.directions() {
+            let (Some(new_r), Some(new_c)) = (r.checked_add_signed(dr), c.checked_add_signed(dc))
+            else {
+                continue;
+            };
+
+            if new_r < self.rows && new_c < self.cols {
+                let next = self.height((new_r, new_c));
+
+                if next != IMPASSABLE && rule.allows(value, next) {
+                    steps.push((new_r, new_c));
+                }
+            }
+        }
+
+        steps
+    }
+
+    fn trailheads(&self, rule: &Rule) -> Vec<Pos> {
+        let mut heads: Vec<Pos> = Vec::new();
+
+        for (r, row) in self.heights.iter().enumerate() {
+            for (c, &val) in row.iter().enumerate() {
+                if val == rule.trailhead {
+                    heads.push((r, c));
+                }
+            }
+        }
+
+        heads
+    }
 }
 
-fn debug_arr(arr: &Arr) -> String {
+fn debug_arr(topo: &Topo) -> String {
     let mut result: String = String::new();
 
-    for r in 0..ROWS {
-        for c in 0..COLS {
-            result += arr[r][c].to_string().as_str();
+    for row in topo.heights.iter() {
+        for &val in row.iter() {
+            if val == IMPASSABLE {
+                result += ".";
+            } else {
+                result += val.to_string().as_str();
+            }
         }
         result += "\n";
     }
@@ -33,9 +123,8 @@ fn debug_arr(arr: &Arr) -> String {
 fn debug_cache(arr: &Cache) -> String {
     let mut result: String = String::new();
 
-    for r in 0..ROWS {
-        for c in 0..COLS {
-            let val = arr[r][c];
+    for row in arr.iter() {
+        for &val in row.iter() {
             if let Some(v) = val {
                 result += v.to_string().as_str();
             } else {
@@ -48,119 +137,253 @@ fn debug_cache(arr: &Cache) -> String {
     result
 }
 
-fn reset_visited(visit: &mut Visited) {
-    for r in 0..ROWS {
-        for c in 0..COLS {
-            visit[r][c] = false;
-        }
-    }
-}
+// Every trail of every trailhead, up to `limit` each, then the shortest and longest
+fn print_trails(topo: &Topo, rule: &Rule, limit: Option<usize>) {
+    let length = |trail: &Option<Trail>| trail.as_ref().map_or(0, Vec::len);
 
-fn find_peaks(arr: &Arr, visit: &mut Visited, peaks: &mut HashSet<(usize, usize)>, (r, c): (usize, usize)) {
-    let value = arr[r][c];
-
-    if value >= 9 {
-        peaks.insert((r, c));
-        return;
-    }
+    for trailhead in topo.trailheads(rule) {
+        for trail in enumerate_trails(topo, rule, trailhead, limit) {
+            println!("{:?}", trail);
+        }
 
-    // Early return for already visited values
-    if visit[r][c] {
-        return;
-    } else {
-        visit[r][c] = true;
+        let summary = summarise(topo, rule, trailhead);
+        println!(
+            "Trailhead {:?} - Trails: {}, Shortest: {}, Longest: {}\n",
+            trailhead,
+            summary.trails,
+            length(&summary.shortest),
+            length(&summary.longest)
+        );
     }
+}
 
-    // Search directions
-    for (dr, dc) in DIRS {
-        let new_r = isize::try_from(r).expect("Failed to convert to isize") + dr;
-        let new_c = isize::try_from(c).expect("Failed to convert to isize") + dc;
+// Only the cells reached from this trailhead are remembered, so each search costs what it
+// explores rather than the size of the map
+fn find_peaks(topo: &Topo, rule: &Rule, peaks: &mut HashSet<Pos>, start: Pos) {
+    let mut visit: HashSet<Pos> = HashSet::new();
+    let mut stack: Vec<Pos> = vec![start];
 
-        if in_bounds(new_r, new_c) {
-            let new_r = usize::try_from(new_r).expect("Failed to convert to usize");
-            let new_c = usize::try_from(new_c).expect("Failed to convert to usize");
+    while let Some((r, c)) = stack.pop() {
+        if topo.is_peak((r, c), rule) {
+            peaks.insert((r, c));
+            continue;
+        }
 
-            if arr[new_r][new_c] == value + 1 {
-                find_peaks(arr, visit, peaks, (new_r, new_c));
-            }
+        // Skip already visited values
+        if !visit.insert((r, c)) {
+            continue;
         }
-    }
 
-    //visit[r][c] = false;
+        // Search directions
+        stack.extend(topo.climbs((r, c), rule));
+    }
 }
 
-fn find_paths(arr: &Arr, cache: &mut Cache, (r, c): (usize, usize)) -> usize {
-    let mut paths = 0;
-    let value = arr[r][c];
+// Number of trails from `start`, each cell's count is cached once all its climbs are
+// counted. Only valid for monotonic rules, where trails can't loop.
+fn find_paths(topo: &Topo, rule: &Rule, cache: &mut Cache, start: Pos) -> usize {
+    let mut stack: Vec<(Pos, bool)> = vec![(start, false)];
 
-    if value >= 9 {
-        return 1;
-    }
-
-    // Early return for cached paths
-    if let Some(p) = cache[r][c] {
-        return p;
-    }
+    while let Some(((r, c), expanded)) = stack.pop() {
+        if cache[r][c].is_some() {
+            continue;
+        }
 
-    // Search directions
-    for (dr, dc) in DIRS {
-        let new_r = isize::try_from(r).expect("Failed to convert to isize") + dr;
-        let new_c = isize::try_from(c).expect("Failed to convert to isize") + dc;
+        if topo.is_peak((r, c), rule) {
+            cache[r][c] = Some(1);
+            continue;
+        }
 
-        if in_bounds(new_r, new_c) {
-            let new_r = usize::try_from(new_r).expect("Failed to convert to usize");
-            let new_c = usize::try_from(new_c).expect("Failed to convert to usize");
+        let climbs = topo.climbs((r, c), rule);
 
-            if arr[new_r][new_c] == value + 1 {
-                paths += find_paths(arr, cache, (new_r, new_c));
-            }
+        if expanded {
+            cache[r][c] = Some(
+                climbs
+                    .iter()
+                    .map(|&(nr, nc)| cache[nr][nc].unwrap_or(0))
+                    .sum(),
+            );
+            continue;
         }
-    }
 
-    cache[r][c] = Some(paths);
+        stack.push(((r, c), true));
+        stack.extend(climbs.into_iter().map(|next| (next, false)));
+    }
 
-    paths
+    cache[start.0][start.1].unwrap_or(0)
 }
 
 fn main() {
-    let topo: Arr = {
+    let topo: Topo = {
         let mut file: File = File::open(INPUT).expect("Failed to open INPUT file");
         let mut contents: String = String::new();
         file.read_to_string(&mut contents)
             .expect("Failed to read contents of INPUT file");
 
-        let mut topo_input: Arr = [[0; COLS]; ROWS];
+        Topo::new(&contents)
+    };
 
-        for (r, row) in contents.split("\n").filter(|l| l.len() > 0).enumerate() {
-            for (c, val) in row.chars().enumerate() {
-                topo_input[r][c] = val.to_string().parse().expect("Failed to parse");
-            }
-        }
+    let args = std::env::args().collect::<Vec<_>>();
+    let value = |flag: &str| {
+        args.iter()
+            .position(|a| a == flag)
+            .map(|i| args.get(i + 1).map(String::as_str).unwrap_or_default())
+    };
+    let number = |flag: &str, default| {
+        value(flag).map_or(default, |v| v.parse().expect("Expected a number"))
+    };
 
-        topo_input
+    let rule = Rule {
+        step: value("--step").map_or(1, |v| v.parse().expect("Expected a number")),
+        tolerance: number("--tolerance", 0),
+        diagonal: args.iter().any(|a| a == "--diagonal"),
+        trailhead: number("--trailhead", Rule::PUZZLE.trailhead),
+        peak: number("--peak", Rule::PUZZLE.peak),
     };
 
-    let mut visit: Visited = [[false; COLS]; ROWS];
-    let mut cache: Cache = [[None; COLS]; ROWS];
+    if let Some(trails) = value("--trails") {
+        print_trails(&topo, &rule, trails.parse().ok());
+        return;
+    }
+
+    let mut cache: Cache = vec![vec![None; topo.cols]; topo.rows];
     let mut peaks_acc: usize = 0;
     let mut paths_acc: usize = 0;
 
-    for r in 0..ROWS {
-        for c in 0..COLS {
-            if topo[r][c] == 0 {
-                reset_visited(&mut visit);
-                let mut peaks: HashSet<(usize, usize)> = HashSet::new(); 
-                
-                find_peaks(&topo, &mut visit, &mut peaks, (r, c));
-                peaks_acc += peaks.len();
+    for (r, c) in topo.trailheads(&rule) {
+        let mut peaks: HashSet<Pos> = HashSet::new();
 
-                paths_acc += find_paths(&topo, &mut cache, (r, c));
-            }
-        }
+        find_peaks(&topo, &rule, &mut peaks, (r, c));
+        peaks_acc += peaks.len();
+
+        paths_acc += match rule.monotonic() {
+            true => find_paths(&topo, &rule, &mut cache, (r, c)),
+            false => enumerate_trails(&topo, &rule, (r, c), None).count(),
+        };
     }
 
-    println!("{}", debug_arr(&topo));
-    println!("{}", debug_cache(&cache));
+    // Full map dumps, only readable for small maps
+    if args.iter().any(|a| a == "--debug") {
+        println!("{}", debug_arr(&topo));
+        println!("{}", debug_cache(&cache));
+    }
     println!("Part 1 - Trail Peak Score {:?}", peaks_acc);
     println!("Part 2 - Trail Paths Score {:?}", paths_acc);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "89010123
+78121874
+87430965
+96549874
+45678903
+32019012
+01329801
+10456732
+";
+
+    // Part 1 and 2 scores, counting trails one by one for rules that can loop
+    fn scores(topo: &Topo, rule: &Rule) -> (usize, usize) {
+        let mut cache: Cache = vec![vec![None; topo.cols]; topo.rows];
+        let (mut peaks_acc, mut paths_acc) = (0, 0);
+
+        for trailhead in topo.trailheads(rule) {
+            let mut peaks: HashSet<Pos> = HashSet::new();
+
+            find_peaks(topo, rule, &mut peaks, trailhead);
+            peaks_acc += peaks.len();
+            paths_acc += match rule.monotonic() {
+                true => find_paths(topo, rule, &mut cache, trailhead),
+                false => enumerate_trails(topo, rule, trailhead, None).count(),
+            };
+        }
+
+        (peaks_acc, paths_acc)
+    }
+
+    #[test]
+    fn sample() {
+        let topo = Topo::new(SAMPLE);
+        let trails: usize = topo
+            .trailheads(&Rule::PUZZLE)
+            .into_iter()
+            .map(|t| enumerate_trails(&topo, &Rule::PUZZLE, t, None).count())
+            .sum();
+
+        assert_eq!(scores(&topo, &Rule::PUZZLE), (36, 81));
+        assert_eq!(trails, 81);
+    }
+
+    #[test]
+    fn rules() {
+        // Two ways round the corner
+        let topo = Topo::new("0123\n9.54\n8765\n");
+        assert_eq!(scores(&topo, &Rule::PUZZLE), (1, 2));
+
+        // Down from the peak instead
+        let downhill = Rule {
+            step: -1,
+            trailhead: 9,
+            peak: 0,
+            ..Rule::PUZZLE
+        };
+        assert_eq!(scores(&topo, &downhill), (1, 2));
+
+        let corner = Topo::new("0.\n.1\n");
+        let diagonal = Rule {
+            diagonal: true,
+            peak: 1,
+            ..Rule::PUZZLE
+        };
+        assert_eq!(
+            scores(
+                &corner,
+                &Rule {
+                    peak: 1,
+                    ..Rule::PUZZLE
+                }
+            ),
+            (0, 0)
+        );
+        assert_eq!(scores(&corner, &diagonal), (1, 1));
+
+        // Flat steps are allowed, so trails wander but never revisit a cell
+        let flat = Topo::new("000\n009\n");
+        let loose = Rule {
+            step: 0,
+            tolerance: 9,
+            ..Rule::PUZZLE
+        };
+        let summary = summarise(&flat, &loose, (0, 0));
+        assert_eq!(summary.shortest.unwrap().len(), 4);
+        assert_eq!(summary.longest.unwrap().len(), 6);
+        assert_eq!(scores(&flat, &loose).0, 5);
+    }
+
+    #[test]
+    fn large_map() {
+        // One long climb, far deeper than a recursive search could go
+        let len = 200_000;
+        let row = (0..len)
+            .map(|h| h.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let topo = Topo::new(&row);
+        let rule = Rule {
+            peak: len - 1,
+            ..Rule::PUZZLE
+        };
+
+        assert_eq!((topo.rows, topo.cols), (1, len));
+        assert_eq!(scores(&topo, &rule), (1, 1));
+        assert_eq!(
+            enumerate_trails(&topo, &rule, (0, 0), None)
+                .next()
+                .map(|t| t.len()),
+            Some(len)
+        );
+    }
+}
@@ -0,0 +1,90 @@
+const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+const ALL_DIRS: [(isize, isize); 8] = [
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+];
+
+// What counts as a hiking trail: where it starts and ends, and which steps it may take
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+    // Height gained by each step, negative to walk downhill
+    pub step: isize,
+    // How far a step's height change may be from `step`
+    pub tolerance: usize,
+    pub diagonal: bool,
+    pub trailhead: usize,
+    // Trails end on reaching this height, or passing it
+    pub peak: usize,
+}
+
+impl Rule {
+    pub const PUZZLE: Rule = Rule {
+        step: 1,
+        tolerance: 0,
+        diagonal: false,
+        trailhead: 0,
+        peak: 9,
+    };
+
+    pub fn directions(&self) -> &'static [(isize, isize)] {
+        if self.diagonal {
+            &ALL_DIRS
+        } else {
+            &ORTHOGONAL
+        }
+    }
+
+    pub fn allows(&self, from: usize, to: usize) -> bool {
+        let change = to as i128 - from as i128;
+
+        (change - self.step as i128).unsigned_abs() <= self.tolerance as u128
+    }
+
+    pub fn is_peak(&self, height: usize) -> bool {
+        if self.step < 0 {
+            height <= self.peak
+        } else {
+            height >= self.peak
+        }
+    }
+
+    // Every step moves the same way in height, so no trail can come back to a cell
+    pub fn monotonic(&self) -> bool {
+        self.step.unsigned_abs() > self.tolerance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps() {
+        assert!(Rule::PUZZLE.allows(3, 4));
+        assert!(!Rule::PUZZLE.allows(3, 5));
+        assert!(!Rule::PUZZLE.allows(3, 3));
+        assert!(Rule::PUZZLE.monotonic());
+
+        let loose = Rule {
+            step: 2,
+            tolerance: 2,
+            ..Rule::PUZZLE
+        };
+        assert!(loose.allows(3, 3) && loose.allows(3, 7) && !loose.allows(3, 8));
+        assert!(!loose.monotonic());
+
+        let downhill = Rule {
+            step: -1,
+            trailhead: 9,
+            peak: 0,
+            ..Rule::PUZZLE
+        };
+        assert!(downhill.allows(4, 3) && downhill.is_peak(0) && !downhill.is_peak(9));
+    }
+}
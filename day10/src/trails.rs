@@ -0,0 +1,197 @@
+use std::collections::HashSet;
+
+use crate::rules::Rule;
+use crate::{Pos, Topo};
+
+pub type Trail = Vec<Pos>;
+
+// Every distinct trail from one trailhead, depth first, found one at a time. A trail
+// ends at the first peak it reaches and never visits a cell twice.
+pub struct Trails<'a> {
+    topo: &'a Topo,
+    rule: Rule,
+    // Each step of the trail so far, with the climbs from it still to try
+    stack: Vec<(Pos, Vec<Pos>)>,
+    on_path: HashSet<Pos>,
+    // A trail that is over before it starts, the trailhead is already a peak
+    peak: Option<Trail>,
+}
+
+impl<'a> Trails<'a> {
+    pub fn new(topo: &'a Topo, rule: &Rule, trailhead: Pos) -> Self {
+        let mut trails = Trails {
+            topo,
+            rule: *rule,
+            stack: Vec::new(),
+            on_path: HashSet::new(),
+            peak: None,
+        };
+
+        if topo.is_peak(trailhead, rule) {
+            trails.peak = Some(vec![trailhead]);
+        } else {
+            trails.push(trailhead);
+        }
+
+        trails
+    }
+
+    // Climbs are reversed so popping tries them in the rule's direction order
+    fn push(&mut self, pos: Pos) {
+        let mut options = self.topo.climbs(pos, &self.rule);
+        options.reverse();
+
+        self.on_path.insert(pos);
+        self.stack.push((pos, options));
+    }
+}
+
+impl Iterator for Trails<'_> {
+    type Item = Trail;
+
+    fn next(&mut self) -> Option<Trail> {
+        if let Some(trail) = self.peak.take() {
+            return Some(trail);
+        }
+
+        while let Some((_, options)) = self.stack.last_mut() {
+            let Some(next) = options.pop() else {
+                if let Some((pos, _)) = self.stack.pop() {
+                    self.on_path.remove(&pos);
+                }
+                continue;
+            };
+
+            if self.on_path.contains(&next) {
+                continue;
+            }
+
+            if self.topo.is_peak(next, &self.rule) {
+                let mut trail: Trail = self.stack.iter().map(|(pos, _)| *pos).collect();
+                trail.push(next);
+                return Some(trail);
+            }
+
+            self.push(next);
+        }
+
+        None
+    }
+}
+
+pub fn enumerate_trails<'a>(
+    topo: &'a Topo,
+    rule: &Rule,
+    trailhead: Pos,
+    limit: Option<usize>,
+) -> impl Iterator<Item = Trail> + 'a {
+    Trails::new(topo, rule, trailhead).take(limit.unwrap_or(usize::MAX))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrailSummary {
+    pub trailhead: Pos,
+    pub trails: usize,
+    // The first found when several share a length
+    pub shortest: Option<Trail>,
+    pub longest: Option<Trail>,
+}
+
+pub fn summarise(topo: &Topo, rule: &Rule, trailhead: Pos) -> TrailSummary {
+    let mut summary = TrailSummary {
+        trailhead,
+        trails: 0,
+        shortest: None,
+        longest: None,
+    };
+
+    for trail in Trails::new(topo, rule, trailhead) {
+        summary.trails += 1;
+
+        if summary
+            .shortest
+            .as_ref()
+            .is_none_or(|s| trail.len() < s.len())
+        {
+            summary.shortest = Some(trail.clone());
+        }
+        if summary
+            .longest
+            .as_ref()
+            .is_none_or(|l| trail.len() > l.len())
+        {
+            summary.longest = Some(trail);
+        }
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "89010123
+78121874
+87430965
+96549874
+45678903
+32019012
+01329801
+10456732
+";
+
+    #[test]
+    fn sample() {
+        let topo = Topo::new(SAMPLE);
+        let mut total = 0;
+
+        for trail in Trails::new(&topo, &Rule::PUZZLE, (0, 2)) {
+            assert_eq!(trail.len(), 10);
+
+            for (i, &(r, c)) in trail.iter().enumerate() {
+                assert_eq!(topo.height((r, c)), i);
+            }
+            for pair in trail.windows(2) {
+                let (a, b) = (pair[0], pair[1]);
+                assert_eq!(a.0.abs_diff(b.0) + a.1.abs_diff(b.1), 1);
+            }
+
+            total += 1;
+        }
+
+        assert_eq!(total, 20);
+        assert_eq!(summarise(&topo, &Rule::PUZZLE, (0, 2)).trails, 20);
+        assert_eq!(
+            summarise(&topo, &Rule::PUZZLE, (0, 2))
+                .shortest
+                .unwrap()
+                .len(),
+            10
+        );
+    }
+
+    #[test]
+    fn limit_and_dead_ends() {
+        let topo = Topo::new(SAMPLE);
+
+        assert_eq!(
+            enumerate_trails(&topo, &Rule::PUZZLE, (0, 2), Some(3)).count(),
+            3
+        );
+        assert_eq!(
+            enumerate_trails(&topo, &Rule::PUZZLE, (0, 2), None).count(),
+            20
+        );
+
+        let stub = Topo::new("0123\n");
+        assert_eq!(summarise(&stub, &Rule::PUZZLE, (0, 0)).trails, 0);
+        assert_eq!(summarise(&stub, &Rule::PUZZLE, (0, 0)).longest, None);
+
+        let peak = Topo::new("9\n");
+        assert_eq!(
+            Trails::new(&peak, &Rule::PUZZLE, (0, 0)).collect::<Vec<_>>(),
+            [vec![(0, 0)]]
+        );
+    }
+}
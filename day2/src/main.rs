@@ -3,71 +3,335 @@ use std::io::prelude::*;
 use std::iter::zip;
 
 const INPUT: &str = "input.txt";
+const MIN_STEP: i32 = 1;
+const MAX_STEP: i32 = 3;
 
-fn report_good(report: &Vec<i32>) -> bool {
-    // Create tuples of pairs [1, 2, 3] [(1,2), (2, 3)]
-    let pairs = zip(report.iter(), report.iter().skip(1)).collect::<Vec<_>>();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ViolationKind {
+    Unchanged,
+    DirectionChange,
+    StepTooLarge,
+}
+
+impl ViolationKind {
+    fn name(&self) -> &'static str {
+        match self {
+            ViolationKind::Unchanged => "unchanged",
+            ViolationKind::DirectionChange => "direction_change",
+            ViolationKind::StepTooLarge => "step_too_large",
+        }
+    }
+}
 
-    // Check increasing and decreasing cases
-    let increasing = pairs.iter().map(|(&a,&b)| if (b - a >= 1) && (b - a <= 3) { true } else { false } ).all(|e| e == true);
-    let decreasing = pairs.iter().map(|(&a,&b)| if (a - b >= 1) && (a - b <= 3) { true } else { false } ).all(|e| e == true);
+// The offending pair is always (index, index + 1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Violation {
+    index: usize,
+    kind: ViolationKind,
+}
 
-    increasing || decreasing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verdict {
+    Safe,
+    // Still unsafe without the dampener, `violation` is what breaks it
+    SafeWithDampener {
+        removed: usize,
+        violation: Violation,
+    },
+    Unsafe {
+        violation: Violation,
+    },
 }
 
-fn report_dampener_good(report: &Vec<i32>) -> bool {
-    for i in 0..report.len() {
-        let modified_report = [&report[..i], &report[i+1..]].concat();
-        if report_good(&modified_report) {
-            return true;
-        }
+#[derive(Debug)]
+struct Diagnostic<'a> {
+    line: usize,
+    levels: &'a [i32],
+    verdict: Verdict,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct ParseError {
+    line: usize,
+    token: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: invalid level {:?}", self.line, self.token)
     }
+}
 
-    false
+fn first_violation(report: &[i32]) -> Option<Violation> {
+    // Direction is set by the first pair, every later pair must agree with it
+    let direction = match report {
+        [a, b, ..] => (b - a).signum(),
+        _ => return None,
+    };
+
+    zip(report.iter(), report.iter().skip(1))
+        .enumerate()
+        .find_map(|(index, (&a, &b))| {
+            let diff = b - a;
+            let kind = if diff == 0 {
+                ViolationKind::Unchanged
+            } else if diff.signum() != direction {
+                ViolationKind::DirectionChange
+            } else if !(MIN_STEP..=MAX_STEP).contains(&diff.abs()) {
+                ViolationKind::StepTooLarge
+            } else {
+                return None;
+            };
+
+            Some(Violation { index, kind })
+        })
 }
 
-fn report_safety(reports: &Vec<Vec<i32>>) -> i32 {
+fn report_good(report: &[i32]) -> bool {
+    first_violation(report).is_none()
+}
+
+fn dampener_removal(report: &[i32]) -> Option<usize> {
+    (0..report.len()).find(|&i| {
+        let modified_report = [&report[..i], &report[i + 1..]].concat();
+        report_good(&modified_report)
+    })
+}
+
+fn diagnose(report: &[i32]) -> Verdict {
+    match first_violation(report) {
+        None => Verdict::Safe,
+        Some(violation) => match dampener_removal(report) {
+            Some(removed) => Verdict::SafeWithDampener { removed, violation },
+            None => Verdict::Unsafe { violation },
+        },
+    }
+}
+
+fn diagnostics(reports: &[(usize, Vec<i32>)]) -> Vec<Diagnostic<'_>> {
+    reports
+        .iter()
+        .map(|(line, levels)| Diagnostic {
+            line: *line,
+            levels,
+            verdict: diagnose(levels),
+        })
+        .collect()
+}
 
-    let score: i32 = reports
+fn report_safety(diagnostics: &[Diagnostic]) -> usize {
+    diagnostics
         .iter()
-        .filter(|l| l.len() > 0)
-        .map(|line| report_good(line))
-        .fold(0i32, |acc, e| if e { acc + 1 } else { acc } );
+        .filter(|d| d.verdict == Verdict::Safe)
+        .count()
+}
 
-    score
+fn report_safety_dampener(diagnostics: &[Diagnostic]) -> usize {
+    diagnostics
+        .iter()
+        .filter(|d| !matches!(d.verdict, Verdict::Unsafe { .. }))
+        .count()
 }
 
-fn report_safety_dampener(reports: &Vec<Vec<i32>>) -> i32 {
+fn parse_reports(contents: &str) -> Result<Vec<(usize, Vec<i32>)>, ParseError> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, s)| !s.trim().is_empty())
+        .map(|(i, s)| {
+            let levels = s
+                .split_whitespace()
+                .map(|v| {
+                    v.parse::<i32>().map_err(|_| ParseError {
+                        line: i + 1,
+                        token: v.to_string(),
+                    })
+                })
+                .collect::<Result<Vec<i32>, _>>()?;
 
-    let score: i32 = reports
+            Ok((i + 1, levels))
+        })
+        .collect()
+}
+
+// "levels 1 -> 2 (2 -> 7), step of 5 outside 1..=3"
+fn describe_violation(levels: &[i32], violation: Violation) -> String {
+    let (i, j) = (violation.index, violation.index + 1);
+    let reason = match violation.kind {
+        ViolationKind::Unchanged => "level did not change".to_string(),
+        ViolationKind::DirectionChange => "direction changed".to_string(),
+        ViolationKind::StepTooLarge => format!(
+            "step of {} outside {}..={}",
+            (levels[j] - levels[i]).abs(),
+            MIN_STEP,
+            MAX_STEP
+        ),
+    };
+
+    format!(
+        "levels {} -> {} ({} -> {}), {}",
+        i, j, levels[i], levels[j], reason
+    )
+}
+
+fn describe(diagnostic: &Diagnostic) -> String {
+    let levels = diagnostic.levels;
+
+    match diagnostic.verdict {
+        Verdict::Safe => format!("line {}: safe", diagnostic.line),
+        Verdict::SafeWithDampener { removed, violation } => format!(
+            "line {}: safe with dampener, removed level {} ({}), unsafe at {}",
+            diagnostic.line,
+            removed,
+            levels[removed],
+            describe_violation(levels, violation)
+        ),
+        Verdict::Unsafe { violation } => format!(
+            "line {}: unsafe at {}",
+            diagnostic.line,
+            describe_violation(levels, violation)
+        ),
+    }
+}
+
+fn to_json(diagnostic: &Diagnostic) -> String {
+    let levels = diagnostic
+        .levels
         .iter()
-        .filter(|l| l.len() > 0)
-        .map(|line| report_good(line) || report_dampener_good(line))
-        .fold(0i32, |acc, e| if e { acc + 1 } else { acc } );
+        .map(|l| l.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let violation_json = |violation: Violation| {
+        format!(
+            r#""violation":{{"pair":[{},{}],"kind":"{}"}}"#,
+            violation.index,
+            violation.index + 1,
+            violation.kind.name()
+        )
+    };
+
+    let verdict = match diagnostic.verdict {
+        Verdict::Safe => r#""verdict":"safe""#.to_string(),
+        Verdict::SafeWithDampener { removed, violation } => format!(
+            r#""verdict":"safe_with_dampener","removed":{},{}"#,
+            removed,
+            violation_json(violation)
+        ),
+        Verdict::Unsafe { violation } => {
+            format!(r#""verdict":"unsafe",{}"#, violation_json(violation))
+        }
+    };
 
-    score
+    format!(
+        r#"{{"line":{},"levels":[{}],{}}}"#,
+        diagnostic.line, levels, verdict
+    )
 }
 
 fn main() {
+    let json = std::env::args().any(|a| a == "--json");
+
     let mut file: File = File::open(INPUT).expect("Failed to open file");
     let mut contents: String = String::new();
     file.read_to_string(&mut contents)
         .expect("Failed to read contents");
 
-    let input_lines: Vec<&str> = contents.split("\n").collect::<Vec<_>>();
+    let reports = parse_reports(&contents).unwrap_or_else(|e| {
+        eprintln!("Failed to parse {}: {}", INPUT, e);
+        std::process::exit(1);
+    });
 
-    let reports: Vec<Vec<i32>> = input_lines
-        .iter()
-        .map(|s| {
-            s.split_whitespace()
-                .map(|v| v.parse::<i32>().unwrap())
-                .collect::<Vec<i32>>()
-        })
-        .collect::<Vec<_>>();
+    let diagnostics = diagnostics(&reports);
 
-    let safety = report_safety(&reports);
-    let safety_with_dampener = report_safety_dampener(&reports);
+    if json {
+        let entries = diagnostics.iter().map(to_json).collect::<Vec<_>>();
+        println!("[{}]", entries.join(",\n"));
+        return;
+    }
+
+    for diagnostic in &diagnostics {
+        println!("{}", describe(diagnostic));
+    }
+
+    let safety = report_safety(&diagnostics);
+    let safety_with_dampener = report_safety_dampener(&diagnostics);
 
     println!("Part 1 - Report Safety: {}", safety);
-    println!("Part 2 - Report Safety With Dampener: {}", safety_with_dampener);
+    println!(
+        "Part 2 - Report Safety With Dampener: {}",
+        safety_with_dampener
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "7 6 4 2 1
+1 2 7 8 9
+9 7 6 2 1
+1 3 2 4 5
+8 6 4 4 1
+1 3 6 7 9
+";
+
+    #[test]
+    fn sample() {
+        let reports = parse_reports(SAMPLE).unwrap();
+        let diagnostics = diagnostics(&reports);
+
+        assert_eq!(report_safety(&diagnostics), 2);
+        assert_eq!(report_safety_dampener(&diagnostics), 4);
+
+        assert_eq!(
+            diagnostics[1].verdict,
+            Verdict::Unsafe {
+                violation: Violation {
+                    index: 1,
+                    kind: ViolationKind::StepTooLarge
+                }
+            }
+        );
+        assert_eq!(
+            diagnostics[3].verdict,
+            Verdict::SafeWithDampener {
+                removed: 1,
+                violation: Violation {
+                    index: 1,
+                    kind: ViolationKind::DirectionChange
+                }
+            }
+        );
+        assert_eq!(
+            diagnostics[4].verdict,
+            Verdict::SafeWithDampener {
+                removed: 2,
+                violation: Violation {
+                    index: 2,
+                    kind: ViolationKind::Unchanged
+                }
+            }
+        );
+
+        assert_eq!(
+            describe(&diagnostics[4]),
+            "line 5: safe with dampener, removed level 2 (4), unsafe at levels 2 -> 3 (4 -> 4), level did not change"
+        );
+        assert_eq!(
+            to_json(&diagnostics[3]),
+            r#"{"line":4,"levels":[1,3,2,4,5],"verdict":"safe_with_dampener","removed":1,"violation":{"pair":[1,2],"kind":"direction_change"}}"#
+        );
+    }
+
+    #[test]
+    fn bad_line() {
+        assert_eq!(
+            parse_reports("1 2 3\n4 x 6\n"),
+            Err(ParseError {
+                line: 2,
+                token: "x".to_string()
+            })
+        );
+    }
 }
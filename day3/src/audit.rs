@@ -0,0 +1,195 @@
+use regex::Regex;
+use std::collections::HashSet;
+
+use crate::engine::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Entry {
+    Accepted {
+        call: Call,
+        // State in effect when the call was executed
+        enabled: bool,
+        standard: u64,
+        qualified: u64,
+    },
+    Rejected(Rejection),
+}
+
+pub fn audit(set: &InstructionSet, input: &str) -> Vec<Entry> {
+    let mut machine = Machine::new();
+
+    set.parse(input.as_bytes())
+        .map(|parsed| match parsed {
+            Ok(call) => {
+                let before = machine;
+                set.get(call.instruction).execute(&call.args, &mut machine);
+
+                Entry::Accepted {
+                    call,
+                    enabled: before.enabled,
+                    standard: machine.standard.accumulator - before.standard.accumulator,
+                    qualified: machine.qualified.accumulator - before.qualified.accumulator,
+                }
+            }
+            Err(rejection) => Entry::Rejected(rejection),
+        })
+        .collect()
+}
+
+pub fn describe(input: &str, entry: &Entry) -> String {
+    match entry {
+        Entry::Accepted {
+            call,
+            enabled,
+            standard,
+            qualified,
+        } => format!(
+            "{:>8}  {:<16} {:<8} +{:<8} +{}",
+            call.span.start,
+            &input[call.span.start..call.span.end],
+            if *enabled { "enabled" } else { "disabled" },
+            standard,
+            qualified
+        ),
+        Entry::Rejected(rejection) => format!(
+            "{:>8}  {:<16} rejected: {}",
+            rejection.span.start,
+            // Junk is a byte at a time, so a span can end inside a multi-byte character
+            format!(
+                "{:?}",
+                String::from_utf8_lossy(
+                    &input.as_bytes()[rejection.span.start..rejection.span.end]
+                )
+            ),
+            rejection.reason
+        ),
+    }
+}
+
+// Independent of the tokenizer, used to confirm both agree on which instructions count
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RegexMatch {
+    pub offset: usize,
+    pub text: String,
+    pub qualified: u64,
+}
+
+pub fn regex_method(input: &str) -> Vec<RegexMatch> {
+    let re: Regex = Regex::new(r"do\(\)|don't\(\)|mul\(([0-9]{1,3}),([0-9]{1,3})\)").unwrap();
+
+    let mut enable = true;
+
+    re.captures_iter(input)
+        .map(|cap| {
+            let whole = cap.get(0).unwrap();
+            let mut qualified = 0;
+
+            match whole.as_str() {
+                "do()" => enable = true,
+                "don't()" => enable = false,
+                _ if enable => {
+                    qualified = cap[1].parse::<u64>().unwrap() * cap[2].parse::<u64>().unwrap()
+                }
+                _ => {}
+            }
+
+            RegexMatch {
+                offset: whole.start(),
+                text: whole.as_str().to_string(),
+                qualified,
+            }
+        })
+        .collect()
+}
+
+// Every instruction that only one of the two paths found, or where they disagree on its value
+pub fn disagreements(input: &str, entries: &[Entry], regex: &[RegexMatch]) -> Vec<String> {
+    let parsed = entries
+        .iter()
+        .filter_map(|entry| match entry {
+            Entry::Accepted {
+                call, qualified, ..
+            } => Some(RegexMatch {
+                offset: call.span.start,
+                text: input[call.span.start..call.span.end].to_string(),
+                qualified: *qualified,
+            }),
+            Entry::Rejected(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    let parsed_set = parsed.iter().collect::<HashSet<_>>();
+    let regex_set = regex.iter().collect::<HashSet<_>>();
+
+    let mut differences = Vec::new();
+
+    for m in &parsed {
+        if !regex_set.contains(m) {
+            differences.push(format!("{:>8}  {:<16} parser only", m.offset, m.text));
+        }
+    }
+
+    for m in regex {
+        if !parsed_set.contains(m) {
+            differences.push(format!("{:>8}  {:<16} regex only", m.offset, m.text));
+        }
+    }
+
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str =
+        "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))mul(4*mul ( 2 , 4 )";
+
+    #[test]
+    fn near_misses() {
+        let set = InstructionSet::standard();
+        let entries = audit(&set, SAMPLE);
+
+        let rejected = entries
+            .iter()
+            .filter_map(|e| match e {
+                Entry::Rejected(r) => Some(&SAMPLE[r.span.start..r.span.end]),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(rejected, ["mul[", "mul(32,64]", "mul(4*", "mul "]);
+
+        let Entry::Accepted {
+            enabled, standard, ..
+        } = &entries[3]
+        else {
+            panic!("Expected mul(5,5) to be accepted");
+        };
+        assert_eq!((*enabled, *standard), (false, 25));
+    }
+
+    #[test]
+    fn agrees_with_regex() {
+        let set = InstructionSet::standard();
+        let entries = audit(&set, SAMPLE);
+
+        assert!(disagreements(SAMPLE, &entries, &regex_method(SAMPLE)).is_empty());
+    }
+
+    #[test]
+    fn non_ascii() {
+        let set = InstructionSet::standard();
+        let input = "mul(é)mul(2,3)ü";
+        let entries = audit(&set, input);
+        let lines = entries
+            .iter()
+            .map(|e| describe(input, e))
+            .collect::<Vec<_>>();
+
+        assert!(lines[0].contains("\"mul(\u{fffd}\""));
+        assert!(lines[0].ends_with(r"rejected: expected number but found '\xc3'"));
+        assert!(lines[1].contains("mul(2,3)"));
+        assert!(disagreements(input, &entries, &regex_method(input)).is_empty());
+    }
+}
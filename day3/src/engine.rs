@@ -0,0 +1,490 @@
+use std::io::Read;
+use std::ops::RangeInclusive;
+
+use crate::lexer::*;
+
+pub const MAX_DIGITS: usize = 3;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Tally {
+    pub accumulator: u64,
+    pub sets: usize,
+}
+
+impl Tally {
+    fn add(&mut self, value: u64) {
+        self.accumulator += value;
+        self.sets += 1;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Machine {
+    pub enabled: bool,
+    // Every result regardless of do/don't
+    pub standard: Tally,
+    // Only results produced while enabled
+    pub qualified: Tally,
+}
+
+impl Machine {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            standard: Tally::default(),
+            qualified: Tally::default(),
+        }
+    }
+
+    pub fn accumulate(&mut self, value: u64) {
+        self.standard.add(value);
+
+        if self.enabled {
+            self.qualified.add(value);
+        }
+    }
+}
+
+pub trait Instruction {
+    fn name(&self) -> &str;
+    fn arity(&self) -> RangeInclusive<usize>;
+    fn execute(&self, args: &[u64], machine: &mut Machine);
+}
+
+pub struct Mul;
+
+impl Instruction for Mul {
+    fn name(&self) -> &str {
+        "mul"
+    }
+
+    fn arity(&self) -> RangeInclusive<usize> {
+        2..=2
+    }
+
+    fn execute(&self, args: &[u64], machine: &mut Machine) {
+        machine.accumulate(args.iter().product());
+    }
+}
+
+pub struct Do;
+
+impl Instruction for Do {
+    fn name(&self) -> &str {
+        "do"
+    }
+
+    fn arity(&self) -> RangeInclusive<usize> {
+        0..=0
+    }
+
+    fn execute(&self, _: &[u64], machine: &mut Machine) {
+        machine.enabled = true;
+    }
+}
+
+pub struct Dont;
+
+impl Instruction for Dont {
+    fn name(&self) -> &str {
+        "don't"
+    }
+
+    fn arity(&self) -> RangeInclusive<usize> {
+        0..=0
+    }
+
+    fn execute(&self, _: &[u64], machine: &mut Machine) {
+        machine.enabled = false;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Call {
+    // Index into the owning InstructionSet
+    pub instruction: usize,
+    pub args: Vec<u64>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RejectReason {
+    Unexpected {
+        expected: String,
+        found: String,
+    },
+    TooManyDigits {
+        digits: usize,
+    },
+    TooManyArgs {
+        max: usize,
+    },
+    Arity {
+        expected: RangeInclusive<usize>,
+        found: usize,
+    },
+}
+
+impl std::fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RejectReason::Unexpected { expected, found } => {
+                write!(f, "expected {} but found {}", expected, found)
+            }
+            RejectReason::TooManyDigits { digits } => {
+                write!(
+                    f,
+                    "operand has {} digits, at most {} allowed",
+                    digits, MAX_DIGITS
+                )
+            }
+            RejectReason::TooManyArgs { max } => write!(f, "more than {} operands", max),
+            RejectReason::Arity { expected, found } => write!(
+                f,
+                "{} operands, expected {}..={}",
+                found,
+                expected.start(),
+                expected.end()
+            ),
+        }
+    }
+}
+
+// A name that looked like the start of an instruction but did not form a valid call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejection {
+    pub instruction: usize,
+    pub reason: RejectReason,
+    pub span: Span,
+}
+
+pub struct InstructionSet {
+    instructions: Vec<Box<dyn Instruction>>,
+}
+
+impl InstructionSet {
+    pub fn new() -> Self {
+        Self {
+            instructions: Vec::new(),
+        }
+    }
+
+    pub fn standard() -> Self {
+        Self::new().with(Mul).with(Do).with(Dont)
+    }
+
+    pub fn with(mut self, instruction: impl Instruction + 'static) -> Self {
+        self.instructions.push(Box::new(instruction));
+        self
+    }
+
+    pub fn get(&self, index: usize) -> &dyn Instruction {
+        self.instructions[index].as_ref()
+    }
+
+    // Memory is corrupted so `xmul(` still counts as `mul(`, take the longest name the identifier ends with
+    fn resolve(&self, ident: &str) -> Option<usize> {
+        self.instructions
+            .iter()
+            .enumerate()
+            .filter(|(_, instr)| ident.ends_with(instr.name()))
+            .max_by_key(|(_, instr)| instr.name().len())
+            .map(|(i, _)| i)
+    }
+
+    pub fn parse<R: Read>(&self, reader: R) -> Parser<'_, R> {
+        let max_ident = self
+            .instructions
+            .iter()
+            .map(|instr| instr.name().len())
+            .max()
+            .unwrap_or(0);
+
+        Parser {
+            set: self,
+            tokens: Lexer::new(reader, max_ident),
+            peeked: None,
+        }
+    }
+
+    pub fn run(&self, calls: impl Iterator<Item = Call>) -> Machine {
+        let mut machine = Machine::new();
+
+        for call in calls {
+            self.get(call.instruction).execute(&call.args, &mut machine);
+        }
+
+        machine
+    }
+
+    // Single pass over the reader, memory use does not grow with the input
+    pub fn evaluate_reader<R: Read>(&self, reader: R) -> std::io::Result<Machine> {
+        let mut parser = self.parse(reader);
+        let machine = self.run(parser.by_ref().filter_map(Result::ok));
+
+        match parser.tokens.take_error() {
+            Some(e) => Err(e),
+            None => Ok(machine),
+        }
+    }
+}
+
+pub struct Parser<'s, R: Read> {
+    set: &'s InstructionSet,
+    tokens: Lexer<R>,
+    peeked: Option<Token>,
+}
+
+impl<R: Read> Parser<'_, R> {
+    fn next_token(&mut self) -> Option<Token> {
+        self.peeked.take().or_else(|| self.tokens.next())
+    }
+
+    // Only consume the next token if it matches, a failed call resumes scanning from that token
+    fn next_token_if(&mut self, pred: impl FnOnce(&TokenKind) -> bool) -> Option<Token> {
+        let token = self.next_token()?;
+
+        if pred(&token.kind) {
+            Some(token)
+        } else {
+            self.peeked = Some(token);
+            None
+        }
+    }
+
+    fn eat(&mut self, kind: TokenKind) -> Option<Span> {
+        self.next_token_if(|k| *k == kind).map(|t| t.span)
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<Span, RejectReason> {
+        let expected = kind.to_string();
+
+        self.eat(kind).ok_or_else(|| self.unexpected(expected))
+    }
+
+    // Called after a failed match, so the offending token (if any) is sitting in `peeked`
+    fn unexpected(&self, expected: String) -> RejectReason {
+        RejectReason::Unexpected {
+            expected,
+            found: self
+                .peeked
+                .as_ref()
+                .map_or("end of input".to_string(), |t| t.kind.to_string()),
+        }
+    }
+
+    fn number(&mut self) -> Result<u64, RejectReason> {
+        let Some(token) = self.next_token_if(|k| matches!(k, TokenKind::Number { .. })) else {
+            return Err(self.unexpected("number".to_string()));
+        };
+
+        match token.kind {
+            TokenKind::Number {
+                digits,
+                value: Some(value),
+            } if digits <= MAX_DIGITS => Ok(value),
+            TokenKind::Number { digits, .. } => Err(RejectReason::TooManyDigits { digits }),
+            _ => unreachable!("Token was checked to be a number"),
+        }
+    }
+
+    fn args(&mut self, arity: &RangeInclusive<usize>) -> Result<(Vec<u64>, usize), RejectReason> {
+        self.expect(TokenKind::LParen)?;
+
+        let mut args = Vec::new();
+
+        if let Some(span) = self.eat(TokenKind::RParen) {
+            return Ok((args, span.end));
+        }
+
+        loop {
+            // Give up as soon as there are too many arguments so `args` stays bounded
+            if args.len() == *arity.end() {
+                return Err(RejectReason::TooManyArgs { max: *arity.end() });
+            }
+            args.push(self.number()?);
+
+            if let Some(span) = self.eat(TokenKind::RParen) {
+                return Ok((args, span.end));
+            }
+
+            if self.eat(TokenKind::Comma).is_none() {
+                return Err(self.unexpected("`,` or `)`".to_string()));
+            }
+        }
+    }
+
+    fn call(&mut self, instruction: usize, start: usize) -> Result<Call, Rejection> {
+        let arity = self.set.get(instruction).arity();
+
+        let result = self.args(&arity).and_then(|(args, end)| {
+            if arity.contains(&args.len()) {
+                Ok((args, end))
+            } else {
+                Err(RejectReason::Arity {
+                    expected: arity.clone(),
+                    found: args.len(),
+                })
+            }
+        });
+
+        match result {
+            Ok((args, end)) => Ok(Call {
+                instruction,
+                args,
+                span: Span { start, end },
+            }),
+            Err(reason) => Err(Rejection {
+                instruction,
+                reason,
+                span: Span {
+                    start,
+                    end: self.tokens.offset(),
+                },
+            }),
+        }
+    }
+}
+
+impl<R: Read> Iterator for Parser<'_, R> {
+    type Item = Result<Call, Rejection>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(token) = self.next_token() {
+            let TokenKind::Ident(ident) = token.kind else {
+                continue;
+            };
+
+            let Some(instruction) = self.set.resolve(&ident) else {
+                continue;
+            };
+
+            let start = token.span.end - self.set.get(instruction).name().len();
+
+            return Some(self.call(instruction, start));
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_1: &str =
+        "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))";
+    const SAMPLE_2: &str =
+        "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+
+    impl InstructionSet {
+        fn evaluate(&self, input: &str) -> Machine {
+            self.evaluate_reader(input.as_bytes()).unwrap()
+        }
+    }
+
+    struct Add;
+
+    impl Instruction for Add {
+        fn name(&self) -> &str {
+            "add"
+        }
+
+        fn arity(&self) -> RangeInclusive<usize> {
+            1..=usize::MAX
+        }
+
+        fn execute(&self, args: &[u64], machine: &mut Machine) {
+            machine.accumulate(args.iter().sum());
+        }
+    }
+
+    #[test]
+    fn samples() {
+        let set = InstructionSet::standard();
+
+        assert_eq!(set.evaluate(SAMPLE_1).standard.accumulator, 161);
+        assert_eq!(set.evaluate(SAMPLE_2).qualified.accumulator, 48);
+    }
+
+    #[test]
+    fn spans() {
+        let set = InstructionSet::standard();
+        let calls = set
+            .parse(SAMPLE_2.as_bytes())
+            .filter_map(Result::ok)
+            .collect::<Vec<_>>();
+
+        assert_eq!(calls[0].span, Span { start: 1, end: 9 });
+        assert_eq!(&SAMPLE_2[calls[1].span.start..calls[1].span.end], "don't()");
+        assert_eq!(&SAMPLE_2[calls[4].span.start..calls[4].span.end], "do()");
+    }
+
+    #[test]
+    fn operand_digits() {
+        let set = InstructionSet::standard();
+
+        assert_eq!(set.evaluate("mul(1234,5)mul(123,4)").standard.sets, 1);
+        assert_eq!(set.evaluate("mul(2,mul(3,4))").standard.accumulator, 12);
+    }
+
+    // Hands out a single byte per read so every token straddles a read boundary
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let Some((first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            if buf.is_empty() {
+                return Ok(0);
+            }
+
+            buf[0] = *first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    struct Broken;
+
+    impl Read for Broken {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("disk on fire"))
+        }
+    }
+
+    #[test]
+    fn streaming() {
+        let set = InstructionSet::standard();
+        let machine = set.evaluate_reader(Trickle(SAMPLE_2.as_bytes())).unwrap();
+
+        assert_eq!(machine, set.evaluate(SAMPLE_2));
+        assert!(set.evaluate_reader(Broken).is_err());
+
+        // Long identifiers are trimmed to their tail without losing the instruction
+        let long = format!("{}mul(3,3)", "x".repeat(100_000));
+        assert_eq!(set.evaluate(&long).standard.accumulator, 9);
+    }
+
+    #[test]
+    fn pluggable() {
+        let set = InstructionSet::standard().with(Add);
+        let machine = set.evaluate("add(1,2,3)don't()mul(2,2)add()add(5)");
+
+        assert_eq!(
+            machine.standard,
+            Tally {
+                accumulator: 15,
+                sets: 3
+            }
+        );
+        assert_eq!(
+            machine.qualified,
+            Tally {
+                accumulator: 6,
+                sets: 1
+            }
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use std::io::{BufReader, ErrorKind, Read};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenKind {
+    // Only the trailing `max_ident` bytes are kept, enough to resolve any instruction name
+    Ident(String),
+    // `value` is None once the digits no longer fit
+    Number { digits: usize, value: Option<u64> },
+    LParen,
+    RParen,
+    Comma,
+    // Anything the instruction language has no use for, one byte at a time
+    Junk(u8),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+impl std::fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenKind::Ident(_) => write!(f, "identifier"),
+            TokenKind::Number { .. } => write!(f, "number"),
+            TokenKind::LParen => write!(f, "`(`"),
+            TokenKind::RParen => write!(f, "`)`"),
+            TokenKind::Comma => write!(f, "`,`"),
+            TokenKind::Junk(b) => write!(f, "'{}'", b.escape_ascii()),
+        }
+    }
+}
+
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'\'' || b == b'_'
+}
+
+pub struct Lexer<R: Read> {
+    reader: BufReader<R>,
+    peeked: Option<u8>,
+    pos: usize,
+    max_ident: usize,
+    error: Option<std::io::Error>,
+}
+
+impl<R: Read> Lexer<R> {
+    pub fn new(reader: R, max_ident: usize) -> Self {
+        Self {
+            reader: BufReader::new(reader),
+            peeked: None,
+            pos: 0,
+            max_ident,
+            error: None,
+        }
+    }
+
+    // Any read error ends the token stream, it is kept here for the caller to collect
+    pub fn take_error(&mut self) -> Option<std::io::Error> {
+        self.error.take()
+    }
+
+    // Byte offset just past the last token handed out
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+
+    fn peek_byte(&mut self) -> Option<u8> {
+        if self.peeked.is_none() && self.error.is_none() {
+            let mut byte = [0u8; 1];
+
+            self.peeked = loop {
+                match self.reader.read(&mut byte) {
+                    Ok(0) => break None,
+                    Ok(_) => break Some(byte[0]),
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(e) => {
+                        self.error = Some(e);
+                        break None;
+                    }
+                }
+            };
+        }
+
+        self.peeked
+    }
+
+    fn next_byte(&mut self) -> Option<u8> {
+        let b = self.peek_byte()?;
+        self.peeked = None;
+        self.pos += 1;
+        Some(b)
+    }
+
+    fn ident(&mut self) -> TokenKind {
+        let mut tail = String::new();
+
+        while let Some(b) = self.peek_byte().filter(|&b| is_ident_byte(b)) {
+            self.next_byte();
+
+            if tail.len() == self.max_ident {
+                tail.remove(0);
+            }
+            tail.push(b as char);
+        }
+
+        TokenKind::Ident(tail)
+    }
+
+    fn number(&mut self) -> TokenKind {
+        let mut digits = 0;
+        let mut value = Some(0u64);
+
+        while let Some(b) = self.peek_byte().filter(u8::is_ascii_digit) {
+            self.next_byte();
+
+            digits += 1;
+            value = value
+                .and_then(|v| v.checked_mul(10))
+                .and_then(|v| v.checked_add((b - b'0') as u64));
+        }
+
+        TokenKind::Number { digits, value }
+    }
+}
+
+impl<R: Read> Iterator for Lexer<R> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.pos;
+        let b = self.peek_byte()?;
+
+        let kind = if is_ident_byte(b) {
+            self.ident()
+        } else if b.is_ascii_digit() {
+            self.number()
+        } else {
+            self.next_byte();
+
+            match b {
+                b'(' => TokenKind::LParen,
+                b')' => TokenKind::RParen,
+                b',' => TokenKind::Comma,
+                _ => TokenKind::Junk(b),
+            }
+        };
+
+        Some(Token {
+            kind,
+            span: Span {
+                start,
+                end: self.pos,
+            },
+        })
+    }
+}
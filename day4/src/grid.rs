@@ -0,0 +1,121 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 8] = [
+        Direction::Up,
+        Direction::UpRight,
+        Direction::Right,
+        Direction::DownRight,
+        Direction::Down,
+        Direction::DownLeft,
+        Direction::Left,
+        Direction::UpLeft,
+    ];
+
+    pub fn delta(&self) -> (isize, isize) {
+        match self {
+            Direction::Up => (-1, 0),
+            Direction::UpRight => (-1, 1),
+            Direction::Right => (0, 1),
+            Direction::DownRight => (1, 1),
+            Direction::Down => (1, 0),
+            Direction::DownLeft => (1, -1),
+            Direction::Left => (0, -1),
+            Direction::UpLeft => (-1, -1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid {
+    rows: usize,
+    cols: usize,
+    cells: Vec<char>,
+}
+
+impl Grid {
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn get(&self, (row, col): (usize, usize)) -> char {
+        self.cells[row * self.cols + col]
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.rows).flat_map(move |row| (0..self.cols).map(move |col| (row, col)))
+    }
+
+    pub fn offset(
+        &self,
+        (row, col): (usize, usize),
+        (dr, dc): (isize, isize),
+    ) -> Option<(usize, usize)> {
+        let r = row.checked_add_signed(dr)?;
+        let c = col.checked_add_signed(dc)?;
+
+        if r < self.rows && c < self.cols {
+            Some((r, c))
+        } else {
+            None
+        }
+    }
+
+    pub fn step(&self, pos: (usize, usize), dir: Direction) -> Option<(usize, usize)> {
+        self.offset(pos, dir.delta())
+    }
+
+    // Walk from `start` until the edge of the grid
+    pub fn ray(
+        &self,
+        start: (usize, usize),
+        dir: Direction,
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        std::iter::successors(Some(start), move |&pos| self.step(pos, dir))
+    }
+}
+
+impl std::str::FromStr for Grid {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = s.lines().filter(|l| !l.is_empty()).collect::<Vec<_>>();
+        let cols = lines.first().map_or(0, |l| l.chars().count());
+
+        let mut cells = Vec::with_capacity(lines.len() * cols);
+
+        for (row, line) in lines.iter().enumerate() {
+            let before = cells.len();
+            cells.extend(line.chars());
+
+            if cells.len() - before != cols {
+                return Err(format!(
+                    "row {} has {} columns, expected {}",
+                    row,
+                    cells.len() - before,
+                    cols
+                ));
+            }
+        }
+
+        Ok(Self {
+            rows: lines.len(),
+            cols,
+            cells,
+        })
+    }
+}
@@ -0,0 +1,120 @@
+use crate::grid::*;
+
+const BLANK: char = '.';
+const RESET: &str = "\x1b[0m";
+// Foreground colours, neighbouring matches cycle through these
+const PALETTE: [u8; 6] = [31, 32, 33, 34, 35, 36];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Plain,
+    // Each match gets its own colour, cells shared by several matches are shown inverted
+    Ansi,
+}
+
+// `matches` holds the cells of each match, a cell outside all of them is blanked
+pub fn render(grid: &Grid, matches: &[Vec<(usize, usize)>], style: Style) -> String {
+    let mut coverage: Vec<Vec<usize>> = vec![Vec::new(); grid.rows() * grid.cols()];
+
+    for (index, cells) in matches.iter().enumerate() {
+        for &(row, col) in cells {
+            coverage[row * grid.cols() + col].push(index);
+        }
+    }
+
+    let mut output = String::new();
+
+    for row in 0..grid.rows() {
+        for col in 0..grid.cols() {
+            let ch = grid.get((row, col));
+
+            match (coverage[row * grid.cols() + col].as_slice(), style) {
+                ([], _) => output.push(BLANK),
+                (_, Style::Plain) => output.push(ch),
+                ([only], Style::Ansi) => output.push_str(&format!(
+                    "\x1b[{}m{}{}",
+                    PALETTE[only % PALETTE.len()],
+                    ch,
+                    RESET
+                )),
+                ([.., last], Style::Ansi) => output.push_str(&format!(
+                    "\x1b[1;7;{}m{}{}",
+                    PALETTE[last % PALETTE.len()],
+                    ch,
+                    RESET
+                )),
+            }
+        }
+
+        output.push('\n');
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::*;
+
+    const SMALL: &str = "..X...
+.SAMX.
+.A..A.
+XMAS.S
+.X....
+";
+
+    const SAMPLE: &str = "MMMSXXMASM
+MSAMXMSMSA
+AMXSXMAAMM
+MSAMASMSMX
+XMASAMXAMM
+XXAMMXXAMA
+SMSMSASXSS
+SAXAMASAAA
+MAMMMXMMMM
+MXMXAXMASX
+";
+
+    const SAMPLE_RENDERED: &str = "....XXMAS.
+.SAMXMS...
+...S..A...
+..A.A.MS.X
+XMASAMX.MM
+X.....XA.A
+S.S.S.S.SS
+.A.A.A.A.A
+..M.M.M.MM
+.X.X.XMASX
+";
+
+    fn word_cells(grid: &Grid) -> Vec<Vec<(usize, usize)>> {
+        find_words(grid, &["XMAS"])
+            .iter()
+            .map(|hit| hit.positions(grid).collect())
+            .collect()
+    }
+
+    #[test]
+    fn plain() {
+        let small: Grid = SMALL.parse().unwrap();
+        assert_eq!(render(&small, &word_cells(&small), Style::Plain), SMALL);
+
+        let sample: Grid = SAMPLE.parse().unwrap();
+        assert_eq!(
+            render(&sample, &word_cells(&sample), Style::Plain),
+            SAMPLE_RENDERED
+        );
+    }
+
+    #[test]
+    fn ansi_overlap() {
+        let grid: Grid = "XMAS\n".parse().unwrap();
+        let matches = vec![vec![(0, 0), (0, 1)], vec![(0, 1), (0, 2)]];
+
+        assert_eq!(
+            render(&grid, &matches, Style::Ansi),
+            "\x1b[31mX\x1b[0m\x1b[1;7;32mM\x1b[0m\x1b[32mA\x1b[0m.\n"
+        );
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::grid::*;
+
+const ROOT: usize = 0;
+
+// Aho-Corasick over chars, every word is matched in a single pass over a line
+pub struct Automaton {
+    goto: Vec<HashMap<char, usize>>,
+    fail: Vec<usize>,
+    // Word indices ending at each node, including those reached through fail links
+    output: Vec<Vec<usize>>,
+    lengths: Vec<usize>,
+}
+
+impl Automaton {
+    pub fn new(words: &[&str]) -> Self {
+        let mut automaton = Self {
+            goto: vec![HashMap::new()],
+            fail: vec![ROOT],
+            output: vec![Vec::new()],
+            lengths: words.iter().map(|w| w.chars().count()).collect(),
+        };
+
+        for (index, word) in words.iter().enumerate() {
+            if word.is_empty() {
+                continue;
+            }
+
+            let mut node = ROOT;
+
+            for ch in word.chars() {
+                node = match automaton.goto[node].get(&ch) {
+                    Some(&next) => next,
+                    None => {
+                        let next = automaton.goto.len();
+                        automaton.goto.push(HashMap::new());
+                        automaton.fail.push(ROOT);
+                        automaton.output.push(Vec::new());
+                        automaton.goto[node].insert(ch, next);
+                        next
+                    }
+                };
+            }
+
+            // Repeated words only report once
+            if automaton.output[node].is_empty() {
+                automaton.output[node].push(index);
+            }
+        }
+
+        // Breadth first so every fail target is finished before it is inherited from
+        let mut queue = automaton.goto[ROOT]
+            .values()
+            .copied()
+            .collect::<VecDeque<_>>();
+
+        while let Some(node) = queue.pop_front() {
+            let edges = automaton.goto[node]
+                .iter()
+                .map(|(&ch, &next)| (ch, next))
+                .collect::<Vec<_>>();
+
+            for (ch, next) in edges {
+                let fail = automaton.step(automaton.fail[node], ch);
+                automaton.fail[next] = fail;
+
+                let inherited = automaton.output[fail].clone();
+                automaton.output[next].extend(inherited);
+
+                queue.push_back(next);
+            }
+        }
+
+        automaton
+    }
+
+    fn step(&self, mut node: usize, ch: char) -> usize {
+        loop {
+            if let Some(&next) = self.goto[node].get(&ch) {
+                return next;
+            }
+            if node == ROOT {
+                return ROOT;
+            }
+            node = self.fail[node];
+        }
+    }
+
+    // Calls `found(word, start)` with the index into `line` where each match begins
+    pub fn scan(&self, line: impl Iterator<Item = char>, mut found: impl FnMut(usize, usize)) {
+        let mut node = ROOT;
+
+        for (i, ch) in line.enumerate() {
+            node = self.step(node, ch);
+
+            for &word in &self.output[node] {
+                found(word, i + 1 - self.lengths[word]);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hit<'w> {
+    pub start: (usize, usize),
+    pub direction: Direction,
+    pub word: &'w str,
+}
+
+impl Hit<'_> {
+    pub fn positions<'g>(&self, grid: &'g Grid) -> impl Iterator<Item = (usize, usize)> + 'g {
+        grid.ray(self.start, self.direction)
+            .take(self.word.chars().count())
+    }
+}
+
+pub fn find_words<'w>(grid: &Grid, words: &[&'w str]) -> Vec<Hit<'w>> {
+    let automaton = Automaton::new(words);
+    let single = words
+        .iter()
+        .map(|w| w.chars().count() == 1)
+        .collect::<Vec<_>>();
+    let mut hits = Vec::new();
+
+    for direction in Direction::ALL {
+        let (dr, dc) = direction.delta();
+
+        // Each line in this direction starts at a cell with nothing behind it
+        let starts = grid
+            .cells()
+            .filter(|&pos| grid.offset(pos, (-dr, -dc)).is_none());
+
+        for start in starts {
+            let line = grid.ray(start, direction).collect::<Vec<_>>();
+
+            automaton.scan(line.iter().map(|&pos| grid.get(pos)), |word, i| {
+                // A single letter reads the same every way, it is only reported going right
+                if single[word] && direction != Direction::Right {
+                    return;
+                }
+
+                hits.push(Hit {
+                    start: line[i],
+                    direction,
+                    word: words[word],
+                })
+            });
+        }
+    }
+
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "MMMSXXMASM
+MSAMXMSMSA
+AMXSXMAAMM
+MSAMASMSMX
+XMASAMXAMM
+XXAMMXXAMA
+SMSMSASXSS
+SAXAMASAAA
+MAMMMXMMMM
+MXMXAXMASX
+";
+
+    #[test]
+    fn sample() {
+        let grid: Grid = SAMPLE.parse().unwrap();
+
+        assert_eq!(find_words(&grid, &["XMAS"]).len(), 18);
+    }
+
+    #[test]
+    fn multiple_words() {
+        let grid: Grid = "ABC\nDEF\n".parse().unwrap();
+        let mut hits = find_words(&grid, &["BC", "ABC", "FB", "AE", "AE", "Q", "", "D"]);
+        hits.sort_by_key(|h| (h.word, h.start));
+
+        assert_eq!(
+            hits,
+            [
+                Hit {
+                    start: (0, 0),
+                    direction: Direction::Right,
+                    word: "ABC"
+                },
+                Hit {
+                    start: (0, 0),
+                    direction: Direction::DownRight,
+                    word: "AE"
+                },
+                Hit {
+                    start: (0, 1),
+                    direction: Direction::Right,
+                    word: "BC"
+                },
+                Hit {
+                    start: (1, 0),
+                    direction: Direction::Right,
+                    word: "D"
+                },
+                Hit {
+                    start: (1, 2),
+                    direction: Direction::UpLeft,
+                    word: "FB"
+                },
+            ]
+        );
+    }
+}
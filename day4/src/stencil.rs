@@ -0,0 +1,188 @@
+use crate::grid::*;
+
+pub const WILDCARD: char = '.';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    Fixed,
+    Rotations,
+    // Rotations of the pattern and of its mirror image
+    All,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Stencil {
+    rows: usize,
+    cols: usize,
+    // None matches any char
+    cells: Vec<Option<char>>,
+}
+
+impl Stencil {
+    fn get(&self, row: usize, col: usize) -> Option<char> {
+        self.cells[row * self.cols + col]
+    }
+
+    fn rotate(&self) -> Stencil {
+        let cells = (0..self.cols)
+            .flat_map(|row| (0..self.rows).map(move |col| (row, col)))
+            .map(|(row, col)| self.get(self.rows - 1 - col, row))
+            .collect();
+
+        Stencil {
+            rows: self.cols,
+            cols: self.rows,
+            cells,
+        }
+    }
+
+    fn reflect(&self) -> Stencil {
+        let cells = (0..self.rows)
+            .flat_map(|row| (0..self.cols).map(move |col| (row, col)))
+            .map(|(row, col)| self.get(row, self.cols - 1 - col))
+            .collect();
+
+        Stencil { cells, ..*self }
+    }
+
+    // Distinct orientations only, a symmetric pattern must not match the same place twice
+    pub fn variants(&self, symmetry: Symmetry) -> Vec<Stencil> {
+        let mut bases = vec![self.clone()];
+        if symmetry == Symmetry::All {
+            bases.push(self.reflect());
+        }
+
+        let turns = match symmetry {
+            Symmetry::Fixed => 1,
+            Symmetry::Rotations | Symmetry::All => 4,
+        };
+
+        let mut variants: Vec<Stencil> = Vec::new();
+
+        for base in bases {
+            let mut current = base;
+
+            for _ in 0..turns {
+                let next = current.rotate();
+                if !variants.contains(&current) {
+                    variants.push(current);
+                }
+                current = next;
+            }
+        }
+
+        variants
+    }
+
+    // Offsets from the top left corner of every cell that has to match
+    pub fn fixed_cells(&self) -> impl Iterator<Item = ((usize, usize), char)> + '_ {
+        (0..self.rows)
+            .flat_map(move |row| (0..self.cols).map(move |col| (row, col)))
+            .filter_map(|(row, col)| self.get(row, col).map(|ch| ((row, col), ch)))
+    }
+
+    fn matches_at(&self, grid: &Grid, (row, col): (usize, usize)) -> bool {
+        row + self.rows <= grid.rows()
+            && col + self.cols <= grid.cols()
+            && self
+                .fixed_cells()
+                .all(|((dr, dc), ch)| grid.get((row + dr, col + dc)) == ch)
+    }
+}
+
+impl std::str::FromStr for Stencil {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let grid: Grid = s.parse()?;
+
+        if grid.rows() == 0 || grid.cols() == 0 {
+            return Err("stencil is empty".to_string());
+        }
+
+        Ok(Stencil {
+            rows: grid.rows(),
+            cols: grid.cols(),
+            cells: grid
+                .cells()
+                .map(|pos| Some(grid.get(pos)).filter(|&ch| ch != WILDCARD))
+                .collect(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement<'s> {
+    pub top_left: (usize, usize),
+    pub stencil: &'s Stencil,
+}
+
+impl Placement<'_> {
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let (row, col) = self.top_left;
+
+        self.stencil
+            .fixed_cells()
+            .map(move |((dr, dc), _)| (row + dr, col + dc))
+    }
+}
+
+pub fn find_stencil<'s>(grid: &Grid, variants: &'s [Stencil]) -> Vec<Placement<'s>> {
+    grid.cells()
+        .flat_map(|pos| {
+            variants
+                .iter()
+                .filter(move |stencil| stencil.matches_at(grid, pos))
+                .map(move |stencil| Placement {
+                    top_left: pos,
+                    stencil,
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = ".M.S......
+..A..MSMS.
+.M.S.MAA..
+..A.ASMSM.
+.M.S.M....
+..........
+S.S.S.S.S.
+.A.A.A.A..
+M.M.M.M.M.
+..........
+";
+
+    #[test]
+    fn x_mas() {
+        let grid: Grid = SAMPLE.parse().unwrap();
+        let stencil: Stencil = "M.S\n.A.\nM.S\n".parse().unwrap();
+
+        assert_eq!(stencil.variants(Symmetry::Fixed).len(), 1);
+        assert_eq!(stencil.variants(Symmetry::All).len(), 4);
+
+        let variants = stencil.variants(Symmetry::Rotations);
+        assert_eq!(find_stencil(&grid, &variants).len(), 9);
+    }
+
+    #[test]
+    fn orientations() {
+        let stencil: Stencil = "AB.\n".parse().unwrap();
+        let rotated: Stencil = "A\nB\n.\n".parse().unwrap();
+
+        assert_eq!(stencil.rotate(), rotated);
+        assert_eq!(stencil.variants(Symmetry::Rotations).len(), 4);
+        // A line's mirror image is one of its rotations
+        assert_eq!(stencil.variants(Symmetry::All).len(), 4);
+
+        let corner: Stencil = "AB\nC.\n".parse().unwrap();
+        assert_eq!(corner.variants(Symmetry::All).len(), 8);
+
+        let plus: Stencil = ".A.\nAAA\n.A.\n".parse().unwrap();
+        assert_eq!(plus.variants(Symmetry::All).len(), 1);
+    }
+}
@@ -1,104 +1,346 @@
-use regex::Regex;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 use std::fs::File;
 use std::io::prelude::*;
 
+mod rules;
+use rules::*;
+
 const INPUT: &str = "input.txt";
 
-fn parse_input(text: String) -> (Vec<(u8, u8)>, Vec<Vec<u8>>) {
-    let rules_re = Regex::new(r"([0-9]*)\|([0-9]*)").unwrap();
-    let pages_re = Regex::new(r"((?:[0-9]*,)+[0-9]*)").unwrap();
-    let hay = text.as_str();
+type Page = u32;
+type Rule = (Page, Page);
+
+#[derive(Debug, PartialEq, Eq)]
+enum OrderingError {
+    // Each page must come before the next, and the last before the first
+    Cycle(Vec<Page>),
+}
 
-    let mut rules: Vec<(u8, u8)> = Vec::new();
-    let mut page_orders: Vec<Vec<u8>> = Vec::new();
+impl std::fmt::Display for OrderingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderingError::Cycle(pages) => {
+                let cycle = pages
+                    .iter()
+                    .chain(pages.first())
+                    .map(|p| p.to_string())
+                    .collect::<Vec<_>>();
 
-    for (_, [num1, num2]) in rules_re.captures_iter(hay).map(|c| c.extract()) {
-        rules.push((num1.parse().unwrap(), num2.parse().unwrap()));
+                write!(f, "rules contain a cycle {}", cycle.join(" -> "))
+            }
+        }
     }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct ParseError {
+    line: usize,
+    reason: String,
+}
 
-    for (_, [st]) in pages_re.captures_iter(hay).map(|c| c.extract()) {
-        page_orders.push(st.split(",").map(|s| s.parse::<u8>().unwrap()).collect());
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
     }
+}
 
-    assert!(
-        page_orders.iter().all(|e| e.len() % 2 == 1),
-        "Even lists are present..."
-    );
+fn parse_page(text: &str, line: usize) -> Result<Page, ParseError> {
+    let error = |reason: String| ParseError { line, reason };
+
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(error(format!("{:?} is not a page number", text)));
+    }
 
-    (rules, page_orders)
+    text.parse()
+        .map_err(|_| error(format!("page {} is too large", text)))
 }
 
-fn rule_holds(rule: &(u8, u8), pages: &Vec<u8>) -> Option<bool> {
-    let mut left: Option<usize> = None;
-    let mut right: Option<usize> = None;
+// Rules as `a|b` one per line, a single blank line, then updates as `a,b,c`
+fn parse_input(text: String) -> Result<(Vec<Rule>, Vec<Vec<Page>>), ParseError> {
+    let mut rules: Vec<Rule> = Vec::new();
+    let mut page_orders: Vec<Vec<Page>> = Vec::new();
+    let mut in_updates = false;
+
+    for (i, l) in text.trim_end().lines().enumerate() {
+        let line = i + 1;
+
+        if l.is_empty() && !in_updates {
+            in_updates = true;
+        } else if in_updates {
+            page_orders.push(
+                l.split(',')
+                    .map(|s| parse_page(s, line))
+                    .collect::<Result<_, _>>()?,
+            );
+        } else {
+            let Some((before, after)) = l.split_once('|') else {
+                return Err(ParseError {
+                    line,
+                    reason: format!("{:?} is not a rule of the form a|b", l),
+                });
+            };
 
-    for (index, &num) in pages.iter().enumerate() {
-        if num == rule.0 {
-            left = Some(index);
+            rules.push((parse_page(before, line)?, parse_page(after, line)?));
         }
+    }
+
+    Ok((rules, page_orders))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MiddlePolicy {
+    Lower,
+    Upper,
+    // Even length updates have no middle page
+    Reject,
+}
+
+fn middle_page(pages: &[Page], policy: MiddlePolicy) -> Option<Page> {
+    let half = pages.len() / 2;
+
+    if pages.len() % 2 == 1 {
+        return pages.get(half).copied();
+    }
+
+    match policy {
+        MiddlePolicy::Lower => pages.get(half.checked_sub(1)?).copied(),
+        MiddlePolicy::Upper => pages.get(half).copied(),
+        MiddlePolicy::Reject => None,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Violation {
+    rule: Rule,
+    before_at: usize,
+    after_at: usize,
+}
+
+// A rule is broken when its `after` page sits in front of its `before` page
+fn violations(index: &RuleIndex, pages: &[Page]) -> Vec<Violation> {
+    let placed = positions(pages);
+    let mut found = Vec::new();
 
-        if num == rule.1 {
-            right = Some(index);
+    for (k, &(before, before_at)) in placed.iter().enumerate() {
+        for &(after, after_at) in &placed[..k] {
+            if index.requires(before, after) {
+                found.push(Violation {
+                    rule: (before, after),
+                    before_at,
+                    after_at,
+                });
+            }
+        }
+    }
+
+    found
+}
+
+fn all_rules_hold(index: &RuleIndex, pages: &[Page]) -> bool {
+    let placed = positions(pages);
+
+    placed.iter().enumerate().all(|(k, &(before, _))| {
+        placed[..k]
+            .iter()
+            .all(|&(after, _)| !index.requires(before, after))
+    })
+}
+
+// Topological sort over the rules that mention two pages of this update.
+// Ties keep the original update order so an already valid update comes back unchanged.
+fn order_indices(index: &RuleIndex, pages: &[Page]) -> Result<Vec<usize>, OrderingError> {
+    let n = pages.len();
+    let mut successors: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut indegree: Vec<usize> = vec![0; n];
+
+    for i in 0..n {
+        for j in (0..n).filter(|&j| j != i && index.requires(pages[i], pages[j])) {
+            successors[i].push(j);
+            predecessors[j].push(i);
+            indegree[j] += 1;
+        }
+    }
+
+    let mut ready: BinaryHeap<Reverse<usize>> =
+        (0..n).filter(|&i| indegree[i] == 0).map(Reverse).collect();
+    let mut placed: Vec<bool> = vec![false; n];
+    let mut ordered: Vec<usize> = Vec::with_capacity(n);
+
+    while let Some(Reverse(i)) = ready.pop() {
+        placed[i] = true;
+        ordered.push(i);
+
+        for &j in &successors[i] {
+            indegree[j] -= 1;
+            if indegree[j] == 0 {
+                ready.push(Reverse(j));
+            }
         }
     }
 
-    if let (Some(l), Some(r)) = (left, right) {
-        // If rule holds
-        Some(l < r)
-    } else {
-        // Rule doesn't apply
-        None
+    if ordered.len() == n {
+        return Ok(ordered);
     }
+
+    // Every page left over still waits on another left over page, walking back
+    // through those predecessors has to revisit a page eventually
+    let mut node = (0..n)
+        .find(|&i| !placed[i])
+        .expect("Some page was not placed");
+    let mut seen_at: Vec<Option<usize>> = vec![None; n];
+    let mut path: Vec<usize> = Vec::new();
+
+    let start = loop {
+        if let Some(k) = seen_at[node] {
+            break k;
+        }
+
+        seen_at[node] = Some(path.len());
+        path.push(node);
+
+        node = *predecessors[node]
+            .iter()
+            .find(|&&p| !placed[p])
+            .expect("Unplaced page has an unplaced predecessor");
+    };
+
+    Err(OrderingError::Cycle(
+        path[start..].iter().rev().map(|&i| pages[i]).collect(),
+    ))
+}
+
+fn order_pages(index: &RuleIndex, pages: &[Page]) -> Result<Vec<Page>, OrderingError> {
+    Ok(order_indices(index, pages)?
+        .into_iter()
+        .map(|i| pages[i])
+        .collect())
 }
 
-fn all_rules_hold(rules: &Vec<(u8, u8)>, pages: &Vec<u8>) -> bool {
-    rules.iter().all(|r| {
-        if let Some(b) = rule_holds(r, pages) {
-            b
+#[derive(Debug, PartialEq, Eq)]
+struct Move {
+    page: Page,
+    // The page it goes straight after, None for the front
+    after: Option<Page>,
+}
+
+// Pages on the longest run already in corrected relative order can stay put,
+// every other page has to be moved once, and no fewer moves will do. Moves are in
+// corrected order, so each one's `after` page is already in place when it's applied.
+fn minimal_moves(pages: &[Page], ordered: &[usize]) -> Vec<Move> {
+    let mut target: Vec<usize> = vec![0; pages.len()];
+    for (to, &from) in ordered.iter().enumerate() {
+        target[from] = to;
+    }
+
+    // Longest increasing subsequence of targets, tails[k] ends the best run of length k + 1
+    let mut tails: Vec<usize> = Vec::new();
+    let mut parent: Vec<Option<usize>> = vec![None; pages.len()];
+
+    for i in 0..pages.len() {
+        let k = tails.partition_point(|&t| target[t] < target[i]);
+
+        parent[i] = k.checked_sub(1).map(|k| tails[k]);
+
+        if k == tails.len() {
+            tails.push(i);
         } else {
-            true
+            tails[k] = i;
         }
-    })
+    }
+
+    let mut stays: Vec<bool> = vec![false; pages.len()];
+    let mut node = tails.last().copied();
+    while let Some(i) = node {
+        stays[i] = true;
+        node = parent[i];
+    }
+
+    ordered
+        .iter()
+        .enumerate()
+        .filter(|&(_, &i)| !stays[i])
+        .map(|(to, &i)| Move {
+            page: pages[i],
+            after: to.checked_sub(1).map(|prev| pages[ordered[prev]]),
+        })
+        .collect()
 }
 
-fn compose_good_page(rules: &Vec<(u8, u8)>, pages: &Vec<u8>) -> Vec<u8> {
-    let mut new_pages: Vec<u8> = Vec::new();
-    let mut test_order;
+fn report(index: &RuleIndex, pages: &[Page]) {
+    println!("Update {}", format_update(pages));
 
-    for val in pages {
-        
-        for i in 0..pages.len()+1 {
-            test_order = new_pages.clone();
-            test_order.insert(i, *val);
+    for v in violations(index, pages) {
+        println!(
+            "  rule {}|{} violated: {} at {}, {} at {}",
+            v.rule.0, v.rule.1, v.rule.0, v.before_at, v.rule.1, v.after_at
+        );
+    }
 
-            if all_rules_hold(rules, &test_order) {
-                new_pages = test_order;
-                break;
+    match order_indices(index, pages) {
+        Ok(ordered) => {
+            for m in minimal_moves(pages, &ordered) {
+                match m.after {
+                    Some(after) => println!("  move {} after {}", m.page, after),
+                    None => println!("  move {} to the front", m.page),
+                }
             }
         }
+        Err(e) => println!("  cannot fix, {}", e),
     }
+}
 
-    new_pages
+fn format_update(pages: &[Page]) -> String {
+    pages
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
 }
 
 fn main() {
+    let args = std::env::args().collect::<Vec<_>>();
+
+    let policy = match args
+        .iter()
+        .position(|a| a == "--middle")
+        .map(|i| args.get(i + 1).map(String::as_str))
+    {
+        None => MiddlePolicy::Reject,
+        Some(Some("lower")) => MiddlePolicy::Lower,
+        Some(Some("upper")) => MiddlePolicy::Upper,
+        Some(Some("reject")) => MiddlePolicy::Reject,
+        Some(other) => panic!("--middle expects lower, upper or reject, got {:?}", other),
+    };
+
     let mut file: File = File::open(INPUT).expect("Failed to open INPUT file");
     let mut contents: String = String::new();
     file.read_to_string(&mut contents)
         .expect("Failed to read contents of INPUT file");
 
-    let (rules, pages) = parse_input(contents);
+    let (rules, pages) = parse_input(contents).unwrap_or_else(|e| {
+        eprintln!("Failed to parse {}: {}", INPUT, e);
+        std::process::exit(1);
+    });
 
     println!("Rules {:?}, Pages {:?}", rules.len(), pages.len());
 
+    let index = RuleIndex::new(&rules);
+
     let valid_pages = pages
         .iter()
-        .filter(|&p| all_rules_hold(&rules, p))
+        .filter(|&p| all_rules_hold(&index, p))
         .collect::<Vec<_>>();
 
-    let accumulator: usize = valid_pages
-        .iter()
-        .fold(0, |acc, &e| acc + usize::from(e[e.len() / 2]));
+    let mut accumulator: u64 = 0;
+
+    for p in &valid_pages {
+        match middle_page(p, policy) {
+            Some(middle) => accumulator += u64::from(middle),
+            None => println!("Update {}: no middle page", format_update(p)),
+        }
+    }
 
     println!(
         "Part 1 - Valid Pages {:?}, Accumulator {:?}",
@@ -108,14 +350,26 @@ fn main() {
 
     let invalid_pages = pages
         .iter()
-        .filter(|&p| !all_rules_hold(&rules, p))
+        .filter(|&p| !all_rules_hold(&index, p))
         .collect::<Vec<_>>();
 
-    let new_good_pages = invalid_pages.iter()
-        .map(|p| compose_good_page(&rules, p))
-        .collect::<Vec<_>>();
+    if args.iter().any(|a| a == "--report") {
+        for p in &invalid_pages {
+            report(&index, p);
+        }
+    }
 
-    let accumulator = new_good_pages.iter().fold(0, |acc, e| acc + usize::from(e[e.len() / 2]));
+    let mut accumulator: u64 = 0;
+
+    for p in &invalid_pages {
+        match order_pages(&index, p) {
+            Ok(ordered) => match middle_page(&ordered, policy) {
+                Some(middle) => accumulator += u64::from(middle),
+                None => println!("Update {}: no middle page", format_update(p)),
+            },
+            Err(e) => println!("Update {}: {}", format_update(p), e),
+        }
+    }
 
     println!(
         "Part 2 - Invalid Pages {:?}, Accumulator {:?}",
@@ -123,3 +377,152 @@ fn main() {
         accumulator
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "47|53
+97|13
+97|61
+97|47
+75|29
+61|13
+75|53
+29|13
+97|29
+53|29
+61|53
+97|53
+61|29
+47|13
+75|47
+97|75
+47|61
+75|61
+47|29
+75|13
+53|13
+
+75,47,61,53,29
+97,61,53,29,13
+75,29,13
+75,97,47,61,53
+61,13,29
+97,13,75,29,47
+";
+
+    #[test]
+    fn sample() {
+        let (rules, pages) = parse_input(SAMPLE.to_string()).unwrap();
+        let index = RuleIndex::new(&rules);
+
+        let ordered = pages
+            .iter()
+            .map(|p| order_pages(&index, p).unwrap())
+            .collect::<Vec<_>>();
+
+        for (p, o) in pages.iter().zip(&ordered) {
+            assert_eq!(all_rules_hold(&index, p), p == o);
+            assert!(all_rules_hold(&index, o));
+        }
+
+        assert_eq!(ordered[3], [97, 75, 47, 61, 53]);
+        assert_eq!(ordered[4], [61, 29, 13]);
+        assert_eq!(ordered[5], [97, 75, 47, 29, 13]);
+    }
+
+    #[test]
+    fn explain() {
+        let (rules, updates) = parse_input(SAMPLE.to_string()).unwrap();
+        let index = RuleIndex::new(&rules);
+        let pages = [97, 13, 75, 29, 47];
+
+        assert_eq!(
+            violations(&index, &pages)
+                .iter()
+                .map(|v| v.rule)
+                .collect::<Vec<_>>(),
+            [(75, 13), (29, 13), (47, 13), (47, 29)]
+        );
+
+        let ordered = order_indices(&index, &pages).unwrap();
+        assert_eq!(
+            minimal_moves(&pages, &ordered),
+            [
+                Move {
+                    page: 29,
+                    after: Some(47)
+                },
+                Move {
+                    page: 13,
+                    after: Some(29)
+                },
+            ]
+        );
+
+        // Applied one after another, the moves give the corrected order
+        for pages in updates {
+            let ordered = order_indices(&index, &pages).unwrap();
+            let mut moved = pages.clone();
+
+            for m in minimal_moves(&pages, &ordered) {
+                moved.retain(|&p| p != m.page);
+                let at = m
+                    .after
+                    .map_or(0, |a| moved.iter().position(|&p| p == a).unwrap() + 1);
+                moved.insert(at, m.page);
+            }
+
+            assert_eq!(moved, order_pages(&index, &pages).unwrap());
+        }
+
+        assert!(minimal_moves(&[1, 2, 3], &[0, 1, 2]).is_empty());
+        assert_eq!(
+            minimal_moves(&[1, 2, 3], &[2, 0, 1]),
+            [Move {
+                page: 3,
+                after: None
+            }]
+        );
+    }
+
+    #[test]
+    fn strict_parser() {
+        assert_eq!(
+            parse_input("100000|2\n\n100000,2\n".to_string()),
+            Ok((vec![(100000, 2)], vec![vec![100000, 2]]))
+        );
+
+        for (text, line) in [
+            ("1|\n\n1,2\n", 1),
+            ("1|2\n3-4\n\n1,2\n", 2),
+            ("1|2\n\n1,,2\n", 3),
+            ("1|2\n\n1,2\n\n3,4\n", 4),
+            ("1|2\n\n 1,2\n", 3),
+            ("1|2\n\n99999999999,2\n", 3),
+        ] {
+            assert_eq!(parse_input(text.to_string()).unwrap_err().line, line);
+        }
+    }
+
+    #[test]
+    fn middle() {
+        assert_eq!(middle_page(&[1, 2, 3], MiddlePolicy::Reject), Some(2));
+        assert_eq!(middle_page(&[1, 2, 3, 4], MiddlePolicy::Lower), Some(2));
+        assert_eq!(middle_page(&[1, 2, 3, 4], MiddlePolicy::Upper), Some(3));
+        assert_eq!(middle_page(&[1, 2, 3, 4], MiddlePolicy::Reject), None);
+        assert_eq!(middle_page(&[], MiddlePolicy::Lower), None);
+    }
+
+    #[test]
+    fn cycle() {
+        let index = RuleIndex::new(&[(1, 2), (2, 3), (3, 1), (4, 1)]);
+
+        assert_eq!(
+            order_pages(&index, &[4, 3, 2, 1]),
+            Err(OrderingError::Cycle(vec![1, 2, 3]))
+        );
+        assert_eq!(order_pages(&index, &[3, 1, 4]), Ok(vec![3, 4, 1]));
+    }
+}
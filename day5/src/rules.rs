@@ -0,0 +1,39 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{Page, Rule};
+
+// Built once per rulebook, each lookup is independent of how many rules there are
+pub struct RuleIndex {
+    successors: HashMap<Page, HashSet<Page>>,
+}
+
+impl RuleIndex {
+    pub fn new(rules: &[Rule]) -> Self {
+        let mut successors: HashMap<Page, HashSet<Page>> = HashMap::new();
+
+        for &(before, after) in rules {
+            successors.entry(before).or_default().insert(after);
+        }
+
+        Self { successors }
+    }
+
+    pub fn requires(&self, before: Page, after: Page) -> bool {
+        self.successors
+            .get(&before)
+            .is_some_and(|after_set| after_set.contains(&after))
+    }
+}
+
+// Each distinct page with the position it last appears at, ordered by that position
+pub fn positions(pages: &[Page]) -> Vec<(Page, usize)> {
+    let mut last: HashMap<Page, usize> = HashMap::with_capacity(pages.len());
+
+    for (index, &page) in pages.iter().enumerate() {
+        last.insert(page, index);
+    }
+
+    let mut placed = last.into_iter().collect::<Vec<_>>();
+    placed.sort_unstable_by_key(|&(_, index)| index);
+    placed
+}
@@ -0,0 +1,306 @@
+use crate::{Game, GameResult, Obstacle, Patrol, Tile};
+
+// Clockwise from up, turning right is the next entry
+const DIRS: [(isize, isize); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+type Pos = (usize, usize);
+
+// Where the guard stops, just in front of an obstacle, when walking straight from
+// each (position, direction). None means the walk leaves the board.
+struct JumpTable {
+    cols: usize,
+    stops: Vec<[Option<Pos>; 4]>,
+}
+
+impl JumpTable {
+    fn new(game: &Game) -> Self {
+        let mut stops: Vec<[Option<Pos>; 4]> = vec![[None; 4]; game.rows * game.cols];
+
+        for (dir, &(dr, dc)) in DIRS.iter().enumerate() {
+            // Visit cells so the neighbour ahead is always filled in first
+            let rows = (0..game.rows).collect::<Vec<_>>();
+            let cols = (0..game.cols).collect::<Vec<_>>();
+            let rows = if dr > 0 {
+                rows.into_iter().rev().collect()
+            } else {
+                rows
+            };
+            let cols = if dc > 0 {
+                cols.into_iter().rev().collect()
+            } else {
+                cols
+            };
+
+            for &row in &rows {
+                for &col in &cols {
+                    stops[row * game.cols + col][dir] = match game.offset((row, col), (dr, dc)) {
+                        None => None,
+                        Some(ahead) if game.is_obstacle(ahead) => Some((row, col)),
+                        Some((r, c)) => stops[r * game.cols + c][dir],
+                    };
+                }
+            }
+        }
+
+        Self {
+            cols: game.cols,
+            stops,
+        }
+    }
+
+    // Same as the table lookup, but with one extra obstacle placed on the board
+    fn stop_with(&self, (row, col): Pos, dir: usize, (xr, xc): Pos) -> Option<Pos> {
+        let stop = self.stops[row * self.cols + col][dir];
+        let (dr, dc) = DIRS[dir];
+
+        // How many steps ahead the extra obstacle is, if it is in the way at all
+        let ahead = if dr == 0 && xr == row {
+            (xc as isize - col as isize) * dc
+        } else if dc == 0 && xc == col {
+            (xr as isize - row as isize) * dr
+        } else {
+            0
+        };
+
+        let reach = match stop {
+            Some((r, c)) => (r.abs_diff(row) + c.abs_diff(col)) as isize,
+            None => isize::MAX,
+        };
+
+        if ahead >= 1 && ahead <= reach {
+            Some((xr.wrapping_add_signed(-dr), xc.wrapping_add_signed(-dc)))
+        } else {
+            stop
+        }
+    }
+}
+
+// Replays from just before the guard would walk into `extra`, jumping between turns.
+// `seen` holds a bitmask of directions per cell and is left cleared for the next call.
+fn loops_with(jumps: &JumpTable, start: Pos, dir: usize, extra: Pos, seen: &mut [u8]) -> bool {
+    let mut touched: Vec<usize> = Vec::new();
+    let mut pos = start;
+    let mut dir = dir;
+
+    let looped = loop {
+        let Some(stop) = jumps.stop_with(pos, dir, extra) else {
+            break false;
+        };
+
+        let index = stop.0 * jumps.cols + stop.1;
+        if seen[index] & (1 << dir) != 0 {
+            break true;
+        }
+        if seen[index] == 0 {
+            touched.push(index);
+        }
+        seen[index] |= 1 << dir;
+
+        pos = stop;
+        dir = (dir + 1) % 4;
+    };
+
+    for index in touched {
+        seen[index] = 0;
+    }
+
+    looped
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Variants {
+    // Obstacle tiles that trap the guard in a loop
+    pub found: Vec<Pos>,
+    // Obstacle tiles where the step limit ran out before the guard exited or looped
+    pub timeouts: Vec<Pos>,
+}
+
+// No guard can start on an obstacle, whichever of them is being simulated
+fn is_guard_start(game: &Game, pos: Pos) -> bool {
+    game.guards.iter().any(|&(start, _)| start == pos)
+}
+
+// Tries an obstacle on every free tile and plays the whole game again, works for any patrol
+pub fn brute_force(game: &Game) -> Variants {
+    let mut variants = Variants::default();
+
+    for row in 0..game.rows {
+        for col in 0..game.cols {
+            if is_guard_start(game, (row, col)) {
+                continue;
+            }
+
+            if let Tile::Empty = game.board[row][col] {
+                let mut variant = game.clone();
+                variant.board[row][col] = Tile::Obstacle(Obstacle::new());
+
+                match variant.play() {
+                    GameResult::ObstacleLoop => variants.found.push((row, col)),
+                    GameResult::Timeout => variants.timeouts.push((row, col)),
+                    GameResult::Exited => {}
+                }
+            }
+        }
+    }
+
+    variants
+}
+
+// Every tile where one new obstacle traps the guard in a loop. Only tiles on the
+// original route can change anything, and the route up to that tile is unaffected.
+// The jump table assumes right turns on the four axes, other patrols are brute forced.
+pub fn loop_obstacles(game: &Game) -> Variants {
+    let start = DIRS.iter().position(|&d| d == game.guard_vector);
+    let Some(mut dir) = start.filter(|_| game.patrol == Patrol::PUZZLE) else {
+        return brute_force(game);
+    };
+
+    let jumps = JumpTable::new(game);
+    let index = |(row, col): Pos| row * game.cols + col;
+
+    let mut visited: Vec<bool> = vec![false; game.rows * game.cols];
+    let mut walked: Vec<u8> = vec![0; game.rows * game.cols];
+    let mut seen: Vec<u8> = vec![0; game.rows * game.cols];
+    let mut found: Vec<Pos> = Vec::new();
+
+    let mut pos = game.guard_position;
+    visited[index(pos)] = true;
+
+    // Stops at the edge, or if the unmodified route already loops
+    while walked[index(pos)] & (1 << dir) == 0 {
+        walked[index(pos)] |= 1 << dir;
+
+        let Some(next) = game.offset(pos, DIRS[dir]) else {
+            break;
+        };
+
+        if game.is_obstacle(next) {
+            dir = (dir + 1) % 4;
+            continue;
+        }
+
+        if !visited[index(next)] {
+            visited[index(next)] = true;
+
+            if !is_guard_start(game, next) && loops_with(&jumps, pos, dir, next, &mut seen) {
+                found.push(next);
+            }
+        }
+
+        pos = next;
+    }
+
+    // Jumping between turns can't run out of steps
+    Variants {
+        found,
+        timeouts: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Turn;
+
+    const SAMPLE: &str = "....#.....
+.........#
+..........
+..#.......
+.......#..
+..........
+.#..^.....
+........#.
+#.........
+......#...
+";
+
+    #[test]
+    fn sample() {
+        let game = Game::new(SAMPLE.to_string()).unwrap();
+        let mut variants = loop_obstacles(&game);
+        variants.found.sort();
+
+        assert_eq!(variants.found.len(), 6);
+        assert_eq!(variants, brute_force(&game));
+    }
+
+    #[test]
+    fn other_patrols() {
+        let game = Game::new(SAMPLE.to_string()).unwrap();
+        let left = game.clone().with_patrol(Patrol {
+            turn: Turn::Left,
+            diagonal: false,
+        });
+
+        assert_eq!(loop_obstacles(&left), brute_force(&left));
+        assert!(!loop_obstacles(&left).found.contains(&left.guard_position));
+
+        // A short step limit gives up on the long walks rather than call them exits
+        let limited = left.with_step_limit(20);
+        let variants = brute_force(&limited);
+        assert!(!variants.timeouts.is_empty());
+        assert!(variants
+            .found
+            .iter()
+            .all(|f| !variants.timeouts.contains(f)));
+    }
+
+    #[test]
+    fn other_guards() {
+        // An obstacle at (6, 3) would trap the first guard, but the second one starts there
+        let game = Game::new(SAMPLE.replacen(".#..^", ".#.<^", 1)).unwrap();
+        let mut variants = loop_obstacles(&game);
+        variants.found.sort();
+
+        assert_eq!(variants.found.len(), 5);
+        assert!(!variants.found.contains(&(6, 3)));
+        assert_eq!(variants, brute_force(&game));
+    }
+
+    // A 16x16 board, about one tile in seven an obstacle, guard facing up near the bottom
+    fn scatter(seed: u64) -> String {
+        let mut state = seed;
+        let mut text = String::new();
+
+        for row in 0..16 {
+            for col in 0..16 {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+
+                text.push(if (row, col) == (12, 7) {
+                    '^'
+                } else if (state >> 33).is_multiple_of(7) {
+                    '#'
+                } else {
+                    '.'
+                });
+            }
+            text.push('\n');
+        }
+
+        text
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut compared = 0;
+
+        for seed in 0..20 {
+            let game = Game::new(scatter(seed)).unwrap();
+
+            // The puzzle guarantees the unmodified guard walks off the board
+            if let GameResult::ObstacleLoop = game.clone().play() {
+                continue;
+            }
+
+            let mut variants = loop_obstacles(&game);
+            variants.found.sort();
+
+            assert_eq!(variants, brute_force(&game));
+            compared += 1;
+        }
+
+        assert!(compared > 0);
+    }
+}
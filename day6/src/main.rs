@@ -1,59 +1,89 @@
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::fs::File;
 use std::io::prelude::*;
 
+mod loops;
+
 const INPUT: &str = "input.txt";
-const ROWS: usize = 130;
-const COLS: usize = 130;
 const ITERATION_SAFETY: usize = 10000;
-const STARTING_VECTOR: (isize, isize) = (-1, 0);
-
-struct Obstacle([bool; 4]);
+const GUARDS: [(char, (isize, isize)); 4] =
+    [('^', (-1, 0)), ('>', (0, 1)), ('v', (1, 0)), ('<', (0, -1))];
 
-impl Copy for Obstacle { }
-
-impl Clone for Obstacle {
-    fn clone(&self) -> Self {
-        Obstacle(self.0.clone())
-    }
-}
+#[derive(Clone, Copy)]
+struct Obstacle([bool; 8]);
 
 impl Obstacle {
     fn new() -> Obstacle {
-        Obstacle([false; 4])
+        Obstacle([false; 8])
     }
 
-    fn hash(v: (isize, isize)) -> usize {
+    // Clockwise from up, None for anything that isn't a single step
+    fn hash(v: (isize, isize)) -> Option<usize> {
         match v {
-            (-1, 0) => 0,
-            (0, 1) => 1,
-            (1, 0) => 2,
-            (0, -1) => 3,
-            _ => panic!("Direction Resolve Failure")
+            (-1, 0) => Some(0),
+            (-1, 1) => Some(1),
+            (0, 1) => Some(2),
+            (1, 1) => Some(3),
+            (1, 0) => Some(4),
+            (1, -1) => Some(5),
+            (0, -1) => Some(6),
+            (-1, -1) => Some(7),
+            _ => None,
         }
     }
 
+    // Hitting the same obstacle from the same direction twice means the guard is looping.
+    // Without a direction to remember, loops are left for the step limit to catch.
     fn hit(&mut self, v: (isize, isize)) -> bool {
-        let hit = self.0[Obstacle::hash(v)];
-        self.0[Obstacle::hash(v)] = true;
+        let Some(index) = Obstacle::hash(v) else {
+            return false;
+        };
+
+        let hit = self.0[index];
+        self.0[index] = true;
         hit
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Turn {
+    Right,
+    Left,
+    Reverse,
+}
+
+impl Turn {
+    fn apply(&self, (dr, dc): (isize, isize)) -> (isize, isize) {
+        match self {
+            Turn::Right => (dc, -dr),
+            Turn::Left => (-dc, dr),
+            Turn::Reverse => (-dr, -dc),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Patrol {
+    turn: Turn,
+    // Guards walk the diagonals, starting 45 degrees clockwise of the way they face
+    diagonal: bool,
+}
+
+impl Patrol {
+    const PUZZLE: Patrol = Patrol {
+        turn: Turn::Right,
+        diagonal: false,
+    };
+}
+
+#[derive(Clone, Copy)]
 enum Tile {
     Empty,
     Visited,
     Obstacle(Obstacle),
 }
 
-impl Copy for Tile { }
-
-impl Clone for Tile {
-    fn clone(&self) -> Self {
-        *self
-    }
-}
-
 impl fmt::Debug for Tile {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -71,9 +101,12 @@ enum MoveResult {
     Ok,
 }
 
+#[derive(PartialEq, Eq)]
 enum GameResult {
     Exited,
     ObstacleLoop,
+    // Gave up after `step_limit` steps without exiting or detecting a loop
+    Timeout,
 }
 
 impl fmt::Debug for GameResult {
@@ -81,109 +114,248 @@ impl fmt::Debug for GameResult {
         match self {
             GameResult::Exited => f.write_str("Exited"),
             GameResult::ObstacleLoop => f.write_str("Loop"),
+            GameResult::Timeout => f.write_str("Timeout"),
         }
     }
 }
 
+type State = ((usize, usize), (isize, isize));
+
+#[derive(Debug)]
+struct PathTrace {
+    // Every state the guard was in, in order, starting where it was placed
+    steps: Vec<State>,
+    // Indices into `steps` right after each turn
+    turns: Vec<usize>,
+    // For a loop, the first step that the guard keeps coming back to
+    cycle_start: Option<usize>,
+    result: GameResult,
+}
+
+impl PathTrace {
+    fn cycle(&self) -> Option<&[State]> {
+        self.cycle_start.map(|start| &self.steps[start..])
+    }
+
+    fn turn_points(&self) -> impl Iterator<Item = State> + '_ {
+        self.turns.iter().map(|&i| self.steps[i])
+    }
+}
+
+#[derive(Clone)]
 struct Game {
-    board: [[Tile; COLS]; ROWS],
+    board: Vec<Vec<Tile>>,
+    rows: usize,
+    cols: usize,
+    // The guard being simulated, one of `guards`
     guard_position: (usize, usize),
     guard_vector: (isize, isize),
+    guards: Vec<State>,
+    patrol: Patrol,
+    step_limit: usize,
 }
 
 impl Game {
     fn new(string_in: String) -> Result<Self, String> {
-        let mut map: [[Tile; COLS]; ROWS] = [[Tile::Empty; COLS]; ROWS];
+        let mut map: Vec<Vec<Tile>> = Vec::new();
+
+        let mut guards: Vec<State> = Vec::new();
 
-        let mut guard_pos: Option<(usize, usize)> = None;
+        for (row, row_text) in string_in.split("\n").filter(|l| !l.is_empty()).enumerate() {
+            let mut map_row: Vec<Tile> = Vec::new();
 
-        for (row, row_text) in string_in.split("\n").filter(|l| l.len() > 0).enumerate() {
             for (col, val) in row_text.chars().enumerate() {
                 if val == '.' {
-                    map[row][col] = Tile::Empty;
+                    map_row.push(Tile::Empty);
                 } else if val == '#' {
-                    map[row][col] = Tile::Obstacle(Obstacle::new());
-                } else if val == '^' {
-                    guard_pos = Some((row, col));
-                    map[row][col] = Tile::Visited;
+                    map_row.push(Tile::Obstacle(Obstacle::new()));
+                } else if let Some(&(_, vector)) = GUARDS.iter().find(|(ch, _)| *ch == val) {
+                    guards.push(((row, col), vector));
+                    map_row.push(Tile::Empty);
                 } else {
                     panic!("Unknown input value");
                 }
             }
+
+            map.push(map_row);
         }
 
-        if let None = guard_pos {
+        let Some(&(guard_pos, guard_vector)) = guards.first() else {
             return Err("Bad Guard Position".to_string());
+        };
+
+        let rows = map.len();
+        let cols = map[0].len();
+
+        if map.iter().any(|r| r.len() != cols) {
+            return Err("Rows are not all the same length".to_string());
         }
 
-        Ok(Self{
+        Ok(Self {
             board: map,
-            guard_position: guard_pos.unwrap(),
-            guard_vector: STARTING_VECTOR,
+            rows,
+            cols,
+            guard_position: guard_pos,
+            guard_vector,
+            guards,
+            patrol: Patrol::PUZZLE,
+            step_limit: ITERATION_SAFETY,
         })
     }
 
-    fn step(&mut self) -> MoveResult {
-        let current_pos: (isize, isize) = (isize::try_from(self.guard_position.0).unwrap(), isize::try_from(self.guard_position.1).unwrap());
-
-        let new_row = usize::try_from(current_pos.0 + self.guard_vector.0);
-        let new_col = usize::try_from(current_pos.1 + self.guard_vector.1);
+    fn with_patrol(mut self, patrol: Patrol) -> Self {
+        if patrol.diagonal {
+            let rotate = |(dr, dc): (isize, isize)| ((dr + dc).signum(), (dc - dr).signum());
 
-        if let (Ok(r), Ok(c)) = (new_row, new_col) {
-            if r >= ROWS || c >= COLS {
-                return MoveResult::Exited;
+            for guard in self.guards.iter_mut() {
+                guard.1 = rotate(guard.1);
             }
+            self.guard_vector = rotate(self.guard_vector);
+        }
 
-            if let Tile::Obstacle(mut ob) = self.board[r][c] {
-                let previous_hit = ob.hit(self.guard_vector);
+        self.patrol = patrol;
+        self
+    }
 
-                if previous_hit {
-                    return MoveResult::ObstacleLoop;
-                }
+    fn with_step_limit(mut self, step_limit: usize) -> Self {
+        self.step_limit = step_limit;
+        self
+    }
 
-                self.board[r][c] = Tile::Obstacle(ob);
-                return MoveResult::Obstacle;
-            }
+    // A copy of the game that simulates another of the guards
+    fn with_guard(&self, index: usize) -> Self {
+        let mut game = self.clone();
+        (game.guard_position, game.guard_vector) = self.guards[index];
+        game
+    }
 
-            self.board[r][c] = Tile::Visited;
-            self.guard_position = (r, c);
-            return MoveResult::Ok;
+    fn is_obstacle(&self, (row, col): (usize, usize)) -> bool {
+        matches!(self.board[row][col], Tile::Obstacle(_))
+    }
+
+    // None once the move would leave the board
+    fn offset(
+        &self,
+        (row, col): (usize, usize),
+        (dr, dc): (isize, isize),
+    ) -> Option<(usize, usize)> {
+        let r = row.checked_add_signed(dr)?;
+        let c = col.checked_add_signed(dc)?;
+
+        (r < self.rows && c < self.cols).then_some((r, c))
+    }
 
-        } else {
+    fn step(&mut self) -> MoveResult {
+        let Some((r, c)) = self.offset(self.guard_position, self.guard_vector) else {
             return MoveResult::Exited;
+        };
+
+        if let Tile::Obstacle(mut ob) = self.board[r][c] {
+            let previous_hit = ob.hit(self.guard_vector);
+
+            if previous_hit {
+                return MoveResult::ObstacleLoop;
+            }
+
+            self.board[r][c] = Tile::Obstacle(ob);
+            return MoveResult::Obstacle;
         }
+
+        self.board[r][c] = Tile::Visited;
+        self.guard_position = (r, c);
+        MoveResult::Ok
+    }
+
+    fn turn(&mut self) {
+        self.guard_vector = self.patrol.turn.apply(self.guard_vector);
     }
 
-    fn turn_right(&mut self) {
-        self.guard_vector = (self.guard_vector.1, -self.guard_vector.0);
+    fn mark_start(&mut self) {
+        let (row, col) = self.guard_position;
+        self.board[row][col] = Tile::Visited;
     }
 
-    fn visited_tiles(&self) -> usize{
-        self.board.iter().map(|l| l.iter().filter(|t| if let Tile::Visited = t { true } else { false }).count()).sum()
+    fn visited_tiles(&self) -> usize {
+        self.board
+            .iter()
+            .map(|l| l.iter().filter(|t| matches!(t, Tile::Visited)).count())
+            .sum()
     }
 
     fn play(&mut self) -> GameResult {
-        let mut state: MoveResult = MoveResult::Ok;
         let mut iterations = 0;
 
+        self.mark_start();
+
         loop {
-            if let MoveResult::Exited = state {
-                return GameResult::Exited;
+            match self.step() {
+                MoveResult::Exited => return GameResult::Exited,
+                MoveResult::Obstacle => self.turn(),
+                MoveResult::ObstacleLoop => return GameResult::ObstacleLoop,
+                MoveResult::Ok => {}
             }
-    
-            state = self.step();
-    
-            if let MoveResult::Obstacle = state {
-                self.turn_right();
+
+            iterations += 1;
+            if iterations > self.step_limit {
+                return GameResult::Timeout;
             }
-    
-            if let MoveResult::ObstacleLoop = state {
-                return GameResult::ObstacleLoop;
+        }
+    }
+}
+
+impl Game {
+    // Same walk as `play`, but keeping every state. A loop is found the moment a
+    // state repeats, so the trace ends exactly one lap after the cycle starts.
+    fn trace(&mut self) -> PathTrace {
+        let mut steps: Vec<State> = vec![(self.guard_position, self.guard_vector)];
+        let mut turns: Vec<usize> = Vec::new();
+        let mut first_seen: HashMap<State, usize> = HashMap::from([(steps[0], 0)]);
+
+        self.mark_start();
+
+        loop {
+            let turned = match self.step() {
+                MoveResult::Exited => {
+                    return PathTrace {
+                        steps,
+                        turns,
+                        cycle_start: None,
+                        result: GameResult::Exited,
+                    }
+                }
+                MoveResult::Obstacle | MoveResult::ObstacleLoop => {
+                    self.turn();
+                    true
+                }
+                MoveResult::Ok => false,
+            };
+
+            let state = (self.guard_position, self.guard_vector);
+
+            if let Some(&start) = first_seen.get(&state) {
+                return PathTrace {
+                    steps,
+                    turns,
+                    cycle_start: Some(start),
+                    result: GameResult::ObstacleLoop,
+                };
             }
 
-            iterations += 1;
-            if iterations > ITERATION_SAFETY {
-                panic!("Iteration limit hit in Game.play");
+            // Only once the state is in `steps`, a loop that closes on a turn isn't recorded
+            if turned {
+                turns.push(steps.len());
+            }
+            first_seen.insert(state, steps.len());
+            steps.push(state);
+
+            // After the state is kept, so a trace cut off right after a turn still shows it
+            if steps.len() > self.step_limit + 1 {
+                return PathTrace {
+                    steps,
+                    turns,
+                    cycle_start: None,
+                    result: GameResult::Timeout,
+                };
             }
         }
     }
@@ -193,12 +365,16 @@ impl fmt::Debug for Game {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut out: String = String::new();
 
-        out += format!("Guard Position: {:?}, Guard Vector: {:?}", self.guard_position, self.guard_vector).as_str();
+        out += format!(
+            "Guard Position: {:?}, Guard Vector: {:?}",
+            self.guard_position, self.guard_vector
+        )
+        .as_str();
         out += "\n";
 
-        for row in 0..self.board.len() {
-            for col in 0..self.board[row].len() {
-                out += format!("{:?}", self.board[row][col]).as_str();
+        for row in &self.board {
+            for tile in row {
+                out += format!("{:?}", tile).as_str();
             }
             out += "\n";
         }
@@ -207,52 +383,98 @@ impl fmt::Debug for Game {
     }
 }
 
-impl Clone for Game {
-    fn clone(&self) -> Self {
-        let mut new_board: [[Tile; COLS]; ROWS] = [[Tile::Empty; COLS]; ROWS];
+// Guards patrol independently, a tile counts if any of them walked over it
+fn part_1(game: &Game) {
+    let mut combined: Game = game.clone();
+
+    for index in 0..game.guards.len() {
+        let mut guard: Game = game.with_guard(index);
+        let game_result: GameResult = guard.play();
 
-        for row in 0..ROWS {
-            for col in 0..COLS {
-                new_board[row][col] = self.board[row][col];
-            }
+        if game.guards.len() > 1 || game_result != GameResult::Exited {
+            println!(
+                "Guard {} at {:?}: {:?}",
+                index, game.guards[index].0, game_result
+            );
         }
 
-        Game {
-            board: new_board,
-            guard_position: self.guard_position,
-            guard_vector: self.guard_vector,
+        for (row, tiles) in guard.board.iter().enumerate() {
+            for (col, tile) in tiles.iter().enumerate() {
+                if let Tile::Visited = tile {
+                    combined.board[row][col] = Tile::Visited;
+                }
+            }
         }
     }
+
+    println!("Part 1 - Visited Tiles: {:?}", combined.visited_tiles());
 }
 
-fn part_1(game: &Game) {
-    let mut game: Game = game.clone();
-    let _game_result: GameResult = game.play();
+// An obstacle counts if it traps any of the guards
+fn part_2(game: &Game) {
+    let mut found: HashSet<(usize, usize)> = HashSet::new();
+    let mut timeouts: HashSet<(usize, usize)> = HashSet::new();
+
+    for index in 0..game.guards.len() {
+        let variants = loops::loop_obstacles(&game.with_guard(index));
+
+        found.extend(variants.found);
+        timeouts.extend(variants.timeouts);
+    }
+
+    timeouts.retain(|pos| !found.contains(pos));
+
+    if !timeouts.is_empty() {
+        println!(
+            "{} obstacles hit the step limit and are not counted, try a higher --limit",
+            timeouts.len()
+        );
+    }
 
-    //println!("{:?}\n{:?}", game, _game_result);
-    println!("Part 1 - Visited Tiles: {:?}", game.visited_tiles());
+    println!("Part 2 - Guard in loop Variants: {:?}", found.len());
 }
 
-fn part_2(game: &Game) {
-    let mut variants = 0;
+fn print_trace(game: &Game) {
+    let trace = game.clone().trace();
 
-    for row in 0..ROWS {
-        for col in 0..COLS {
-            if let Tile::Empty = game.board[row][col] {
+    println!(
+        "{:?} after {} steps and {} turns",
+        trace.result,
+        trace.steps.len(),
+        trace.turns.len()
+    );
 
-                let mut variant = game.clone();
-                variant.board[row][col] = Tile::Obstacle(Obstacle::new());
-                let game_result = variant.play();
+    for (pos, vector) in trace.turn_points() {
+        println!("turn at {:?}, now facing {:?}", pos, vector);
+    }
 
-                if let GameResult::ObstacleLoop = game_result {
-                    variants += 1;
-                    //println!("{:?}", variant);
-                }
-            }
-        }
+    if let (Some(start), Some(cycle)) = (trace.cycle_start, trace.cycle()) {
+        println!("cycle of {} steps begins at step {}", cycle.len(), start);
     }
+}
 
-    println!("Part 2 - Guard in loop Variants: {:?}", variants);
+// One line per obstacle that traps the guard: row,col,cycle start,cycle length.
+// Only the first guard is traced.
+fn export_loops(game: &Game) {
+    if game.guards.len() > 1 {
+        eprintln!(
+            "Exporting loops for the first of {} guards",
+            game.guards.len()
+        );
+    }
+
+    for (row, col) in loops::loop_obstacles(game).found {
+        let mut variant = game.clone();
+        variant.board[row][col] = Tile::Obstacle(Obstacle::new());
+
+        let trace = variant.trace();
+
+        match (trace.cycle_start, trace.cycle()) {
+            (Some(start), Some(cycle)) => println!("{},{},{},{}", row, col, start, cycle.len()),
+            // Step limit ran out before the cycle closed
+            _ => println!("{},{},,", row, col),
+        }
+    }
 }
 
 fn main() {
@@ -261,9 +483,162 @@ fn main() {
     file.read_to_string(&mut contents)
         .expect("Failed to read contents of INPUT file");
 
-    let game: Game = Game::new(contents).unwrap();
+    let args = std::env::args().collect::<Vec<_>>();
+    let value = |flag: &str| {
+        args.iter()
+            .position(|a| a == flag)
+            .map(|i| args.get(i + 1).map(String::as_str).unwrap_or_default())
+    };
+
+    let turn = match value("--turn") {
+        None | Some("right") => Turn::Right,
+        Some("left") => Turn::Left,
+        Some("reverse") => Turn::Reverse,
+        Some(other) => panic!("--turn expects right, left or reverse, got {:?}", other),
+    };
+
+    let patrol = Patrol {
+        turn,
+        diagonal: args.iter().any(|a| a == "--diagonal"),
+    };
+
+    let step_limit = value("--limit").map_or(ITERATION_SAFETY, |v| {
+        v.parse().expect("--limit expects a number of steps")
+    });
+
+    let game: Game = Game::new(contents)
+        .unwrap()
+        .with_patrol(patrol)
+        .with_step_limit(step_limit);
+
+    if std::env::args().any(|a| a == "--trace") {
+        print_trace(&game);
+        return;
+    }
+
+    if std::env::args().any(|a| a == "--loops") {
+        export_loops(&game);
+        return;
+    }
 
     part_1(&game);
     part_2(&game);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    const SAMPLE: &str = "....#.....
+.........#
+..........
+..#.......
+.......#..
+..........
+.#..^.....
+........#.
+#.........
+......#...
+";
+
+    #[test]
+    fn trace_exit() {
+        let mut game = Game::new(SAMPLE.to_string()).unwrap();
+        let trace = game.trace();
+
+        assert!(matches!(trace.result, GameResult::Exited));
+        assert_eq!(trace.cycle(), None);
+        assert_eq!(
+            trace
+                .steps
+                .iter()
+                .map(|s| s.0)
+                .collect::<HashSet<_>>()
+                .len(),
+            41
+        );
+        assert_eq!(trace.turn_points().next(), Some(((1, 4), (0, 1))));
+    }
+
+    #[test]
+    fn trace_loop() {
+        let mut game = Game::new(SAMPLE.to_string()).unwrap();
+        game.board[6][3] = Tile::Obstacle(Obstacle::new());
+
+        let trace = game.trace();
+        let cycle = trace.cycle().unwrap();
 
-}
\ No newline at end of file
+        assert!(matches!(trace.result, GameResult::ObstacleLoop));
+        // The guard starts inside the loop it gets stuck in
+        assert_eq!(trace.cycle_start, Some(0));
+        assert_eq!(cycle.len(), trace.steps.len());
+    }
+
+    #[test]
+    fn trace_loop_on_turn() {
+        // The last turn puts the guard back where it started, facing the same way
+        let mut game = Game::new(".#...\n....#\n.....\n#^...\n...#.\n".to_string()).unwrap();
+        let trace = game.trace();
+
+        assert_eq!(trace.result, GameResult::ObstacleLoop);
+        assert_eq!(trace.cycle_start, Some(0));
+        assert!(trace.turns.iter().all(|&i| i < trace.steps.len()));
+        assert_eq!(trace.turn_points().count(), 3);
+    }
+
+    #[test]
+    fn patrols() {
+        let game = Game::new(SAMPLE.to_string()).unwrap();
+
+        // Turning left in the sample walks straight back off the top
+        let mut left = game.clone().with_patrol(Patrol {
+            turn: Turn::Left,
+            diagonal: false,
+        });
+        assert_eq!(left.play(), GameResult::Exited);
+
+        // Bouncing between two obstacles never ends, reverse must come back as a loop
+        let mut bounce = Game::new("....#\n.....\n..^..\n.....\n#....\n".to_string())
+            .unwrap()
+            .with_patrol(Patrol {
+                turn: Turn::Reverse,
+                diagonal: true,
+            });
+        assert_eq!(bounce.guard_vector, (-1, 1));
+        assert_eq!(bounce.play(), GameResult::ObstacleLoop);
+        assert_eq!(bounce.visited_tiles(), 3);
+
+        let mut stuck = Game::new("#^.#\n".to_string())
+            .unwrap()
+            .with_patrol(Patrol {
+                turn: Turn::Reverse,
+                diagonal: false,
+            });
+        stuck.guard_vector = (0, 1);
+        assert_eq!(stuck.play(), GameResult::ObstacleLoop);
+    }
+
+    #[test]
+    fn timeout() {
+        let mut game = Game::new(SAMPLE.to_string()).unwrap().with_step_limit(10);
+
+        assert_eq!(game.clone().play(), GameResult::Timeout);
+        assert_eq!(game.trace().result, GameResult::Timeout);
+
+        // Five steps up, then the limit runs out as the guard turns
+        let mut game = Game::new(SAMPLE.to_string()).unwrap().with_step_limit(5);
+        let trace = game.trace();
+        assert_eq!(trace.result, GameResult::Timeout);
+        assert_eq!(trace.steps.len(), 7);
+        assert_eq!(trace.turn_points().collect::<Vec<_>>(), [((1, 4), (0, 1))]);
+    }
+
+    #[test]
+    fn multiple_guards() {
+        let game = Game::new("...\n^.<\n...\n".to_string()).unwrap();
+
+        assert_eq!(game.guards, [((1, 0), (-1, 0)), ((1, 2), (0, -1))]);
+        assert_eq!(game.with_guard(1).guard_vector, (0, -1));
+    }
+}
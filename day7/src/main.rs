@@ -3,57 +3,321 @@ use std::fs::File;
 use std::io::prelude::*;
 use std::iter::zip;
 
+mod operators;
+use operators::*;
+
 const INPUT: &str = "input.txt";
-const USE_CONCAT: bool = true;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Solver {
+    // Tries every operator left to right from the first term
+    Forward,
+    // Undoes operators from the target back towards the first term, only taking the
+    // branches where an inverse exists. Needs every operator to be invertible.
+    Reverse,
+}
+
+impl std::str::FromStr for Solver {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "forward" => Ok(Solver::Forward),
+            "reverse" => Ok(Solver::Reverse),
+            _ => Err(format!("Unknown solver {:?}", s)),
+        }
+    }
+}
+
+type Found<'f> = &'f mut dyn FnMut(&[usize]) -> bool;
 
 struct Equation {
-    target: isize,
-    terms: Vec<isize>,
+    target: Value,
+    terms: Vec<Value>,
 }
 
 impl fmt::Debug for Equation {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}: {:?}", self.target, self.terms)   
+        write!(f, "{:?}: {:?}", self.target, self.terms)
+    }
+}
+
+// The operators that reach the target, one between each pair of terms
+struct Witness<'e, 'o> {
+    terms: &'e [Value],
+    ops: Vec<&'o dyn Operator>,
+}
+
+impl fmt::Display for Witness<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.terms[0])?;
+
+        for (op, term) in self.ops.iter().zip(&self.terms[1..]) {
+            write!(f, " {} {}", op.symbol(), term)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Everything the search turns up for one equation and operator set
+struct Analysis<'e, 'o> {
+    solutions: u64,
+    // Operators that appear in every solution, empty when there are none
+    required: Vec<&'o dyn Operator>,
+    witness: Option<Witness<'e, 'o>>,
+}
+
+impl Analysis<'_, '_> {
+    fn verdict(&self) -> &'static str {
+        match self.solutions {
+            0 => "unsolvable",
+            1 => "unique",
+            _ => "ambiguous",
+        }
     }
 }
 
 impl Equation {
-    fn eval(&self) -> bool {
-        Self::recursive_eval(self.terms[0], &self.terms[1..], self.target)
+    fn solve<'o>(&self, ops: &[&'o dyn Operator], solver: Solver) -> Option<Witness<'_, 'o>> {
+        let mut first: Option<Vec<usize>> = None;
+
+        self.search(ops, solver, &mut |chosen| {
+            first = Some(chosen.to_vec());
+            true
+        });
+
+        first.map(|chosen| self.witness(ops, &chosen))
     }
 
-    fn recursive_eval(acc: isize, terms: &[isize], target: isize) -> bool {
-        if terms.len() <= 0 {
-            return acc == target;
+    fn analyse<'o>(&self, ops: &[&'o dyn Operator], solver: Solver) -> Analysis<'_, 'o> {
+        let mut solutions: u64 = 0;
+        let mut first: Option<Vec<usize>> = None;
+        let mut required: Vec<bool> = vec![true; ops.len()];
+
+        self.search(ops, solver, &mut |chosen| {
+            solutions += 1;
+            first.get_or_insert_with(|| chosen.to_vec());
+
+            for (index, needed) in required.iter_mut().enumerate() {
+                *needed &= chosen.contains(&index);
+            }
+            false
+        });
+
+        Analysis {
+            solutions,
+            required: match solutions {
+                0 => Vec::new(),
+                _ => zip(ops, required)
+                    .filter(|(_, needed)| *needed)
+                    .map(|(&op, _)| op)
+                    .collect(),
+            },
+            witness: first.map(|chosen| self.witness(ops, &chosen)),
         }
+    }
+
+    fn witness<'o>(&self, ops: &[&'o dyn Operator], chosen: &[usize]) -> Witness<'_, 'o> {
+        Witness {
+            terms: &self.terms,
+            ops: chosen.iter().map(|&index| ops[index]).collect(),
+        }
+    }
+
+    // Calls `found` with the index into `ops` of each operator, in order, for every
+    // solution until it returns true. Returns whether the search was stopped.
+    fn search(&self, ops: &[&dyn Operator], solver: Solver, found: Found) -> bool {
+        // Growing only holds for positive terms, a zero can bring a product back down
+        let prune = ops.iter().all(|op| op.grows()) && self.terms.iter().all(|&t| t > 0);
+        let mut chosen: Vec<usize> = vec![0; self.terms.len() - 1];
+
+        match solver {
+            Solver::Forward => Self::recursive_eval(
+                self.terms[0],
+                &self.terms[1..],
+                Some(self.target),
+                ops,
+                prune,
+                &mut chosen,
+                found,
+            ),
+            Solver::Reverse => {
+                Self::reverse_eval(self.target, &self.terms, ops, prune, &mut chosen, found)
+            }
+        }
+    }
+
+    // With no target every combination that evaluates counts, for a prefix whose value
+    // doesn't matter
+    fn recursive_eval(
+        acc: Value,
+        terms: &[Value],
+        target: Option<Value>,
+        ops: &[&dyn Operator],
+        prune: bool,
+        chosen: &mut [usize],
+        found: Found,
+    ) -> bool {
+        let Some((&term, rest)) = terms.split_first() else {
+            return target.is_none_or(|target| acc == target) && found(chosen);
+        };
 
-        if acc > target {
+        if prune && target.is_some_and(|target| acc > target) {
             return false;
         }
 
-        let mut result: bool;
+        let slot = chosen.len() - terms.len();
 
-        result = Self::recursive_eval(acc * terms[0], &terms[1..], target);
-        
-        if result {
-            return result;
+        for (index, op) in ops.iter().enumerate() {
+            let Some(value) = op.apply(acc, term) else {
+                continue;
+            };
+
+            chosen[slot] = index;
+            if Self::recursive_eval(value, rest, target, ops, prune, chosen, found) {
+                return true;
+            }
         }
 
-        result = Self::recursive_eval(acc + terms[0], &terms[1..], target);
+        false
+    }
+
+    // `value` is what `terms` has to produce, operators are chosen from the last one back
+    fn reverse_eval(
+        value: Value,
+        terms: &[Value],
+        ops: &[&dyn Operator],
+        prune: bool,
+        chosen: &mut [usize],
+        found: Found,
+    ) -> bool {
+        let Some((&term, rest)) = terms.split_last() else {
+            return false;
+        };
+
+        if rest.is_empty() {
+            return value == term && found(chosen);
+        }
 
-        if result {
-            return result;
+        // Growing operators can't reach a negative value from positive terms
+        if prune && value < 0 {
+            return false;
         }
 
-        if USE_CONCAT {
-        let concat_val: String = String::new() + acc.to_string().as_str() + terms[0].to_string().as_str();
-        let concat_val: isize = concat_val.parse().expect("Failed to concat numbers");
+        for (index, op) in ops.iter().enumerate() {
+            chosen[rest.len() - 1] = index;
+
+            // Whatever the terms before come to, so they are searched forwards with the
+            // operators already chosen after them kept
+            if op.absorbs(term) {
+                if op.apply(0, term) != Some(value) {
+                    continue;
+                }
+
+                let after = chosen[rest.len() - 1..].to_vec();
+                let mut prefix: Vec<usize> = vec![0; rest.len() - 1];
+                let mut whole = |prefix: &[usize]| found(&[prefix, &after].concat());
 
-        result = Self::recursive_eval(concat_val, &terms[1..], target);
+                if Self::recursive_eval(
+                    rest[0],
+                    &rest[1..],
+                    None,
+                    ops,
+                    false,
+                    &mut prefix,
+                    &mut whole,
+                ) {
+                    return true;
+                }
+                continue;
+            }
+
+            let Some(before) = op.invert(value, term) else {
+                continue;
+            };
+
+            if Self::reverse_eval(before, rest, ops, prune, chosen, found) {
+                return true;
+            }
         }
 
-        result
+        false
+    }
+}
+
+fn parse_equations(contents: &str) -> Vec<Equation> {
+    let mut equations: Vec<Equation> = Vec::new();
+    let lines: Vec<&str> = contents.split("\n").filter(|l| !l.is_empty()).collect();
+
+    for line in lines {
+        let (target, terms) = line.split_once(":").expect("No ':' to split on");
+        let target: Value = target.parse().expect("Could not parse target");
+
+        let terms: Vec<Value> = terms
+            .split_whitespace()
+            .map(|t| t.parse::<Value>().expect("Could not parse term"))
+            .collect();
+
+        assert!(!terms.is_empty(), "Equation without terms");
+
+        equations.push(Equation { target, terms });
     }
+
+    equations
+}
+
+// One row per equation, then how many of each verdict and the calibration total
+fn print_summary(equations: &[Equation], ops: &[&dyn Operator], solver: Solver) {
+    let mut verdicts: Vec<(&str, usize)> = vec![("unique", 0), ("ambiguous", 0), ("unsolvable", 0)];
+    let mut accumulator: Value = 0;
+
+    println!(
+        "{:>20} {:>5} {:>9} {:<10} {:<8} witness",
+        "target", "terms", "solutions", "verdict", "needs"
+    );
+
+    for eq in equations {
+        let analysis = eq.analyse(ops, solver);
+
+        let needs = match analysis.required.as_slice() {
+            [] => "-".to_string(),
+            required => required
+                .iter()
+                .map(|op| op.symbol())
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+        let witness = match &analysis.witness {
+            Some(witness) => witness.to_string(),
+            None => "-".to_string(),
+        };
+
+        println!(
+            "{:>20} {:>5} {:>9} {:<10} {:<8} {}",
+            eq.target,
+            eq.terms.len(),
+            analysis.solutions,
+            analysis.verdict(),
+            needs,
+            witness
+        );
+
+        if analysis.solutions > 0 {
+            accumulator += eq.target;
+        }
+        for (verdict, count) in verdicts.iter_mut() {
+            if *verdict == analysis.verdict() {
+                *count += 1;
+            }
+        }
+    }
+
+    println!();
+    for (verdict, count) in verdicts {
+        println!("{:<10} {}", verdict, count);
+    }
+    println!("{:?}", accumulator);
 }
 
 fn main() {
@@ -63,34 +327,191 @@ fn main() {
         file.read_to_string(&mut contents)
             .expect("Failed to read contents of INPUT file");
 
-        let mut equations: Vec<Equation> = Vec::new();
-        let lines: Vec<&str> = contents.split("\n").filter(|l| l.len() > 0).collect();
+        parse_equations(&contents)
+    };
+
+    let args = std::env::args().collect::<Vec<_>>();
+    let value = |flag: &str| {
+        args.iter()
+            .position(|a| a == flag)
+            .map(|i| args.get(i + 1).map(String::as_str).unwrap_or_default())
+    };
 
-        for line in lines {
-            let (target, terms) = line.split_once(":").expect("No ':' to split on");
-            let target: isize = target.parse().expect("Could not parse target");
+    let ops: Vec<&dyn Operator> = match value("--ops") {
+        Some(symbols) => parse_set(symbols).unwrap(),
+        None => PART_2.to_vec(),
+    };
 
-            let terms: Vec<isize> = terms
-                .split_whitespace()
-                .filter(|t| t.len() > 0)
-                .map(|t| t.parse::<isize>().expect("Could not parse term"))
-                .collect();
+    let solver: Solver = match value("--solver") {
+        Some(name) => name.parse().unwrap(),
+        None => Solver::Forward,
+    };
 
-            equations.push(Equation {
-                target: target,
-                terms: terms,
-            });
+    if solver == Solver::Reverse {
+        if let Some(op) = ops.iter().find(|op| !op.invertible()) {
+            panic!("The reverse solver can't undo {:?}", op);
         }
+    }
 
-        equations
-    };
+    if args.iter().any(|a| a == "--summary") {
+        print_summary(&equations, &ops, solver);
+        return;
+    }
 
-    let evals = zip(equations.iter(), equations.iter().map(|e| e.eval())).collect::<Vec<_>>();
-    let accumulator: isize = evals.iter().filter(|e| e.1 ).map(|e| e.0.target ).sum();
+    let mut accumulator: Value = 0;
 
-    for (eq, eq_bool) in evals {
-        println!{"{:?} - {:?}", eq, eq_bool};
+    for eq in equations.iter() {
+        match eq.solve(&ops, solver) {
+            Some(witness) => {
+                accumulator += eq.target;
+                println!("{:?} - {}", eq, witness);
+            }
+            None => println!("{:?} - false", eq),
+        }
     }
 
     println!("{:?}", accumulator);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PART_1: &[&dyn Operator] = &[&Mul, &Add];
+
+    const SAMPLE: &str = "190: 10 19
+3267: 81 40 27
+83: 17 5
+156: 15 6
+7290: 6 8 6 15
+161011: 16 10 13
+192: 17 8 14
+21037: 9 7 18 13
+292: 11 6 16 20
+";
+
+    fn calibration(ops: &[&dyn Operator], solver: Solver) -> Value {
+        parse_equations(SAMPLE)
+            .iter()
+            .filter(|e| e.solve(ops, solver).is_some())
+            .map(|e| e.target)
+            .sum()
+    }
+
+    #[test]
+    fn sample() {
+        for solver in [Solver::Forward, Solver::Reverse] {
+            assert_eq!(calibration(PART_1, solver), 3749);
+            assert_eq!(calibration(PART_2, solver), 11387);
+        }
+    }
+
+    #[test]
+    fn witness() {
+        let equations = parse_equations(SAMPLE);
+
+        let witness = equations[1].solve(&[&Add, &Mul], Solver::Forward).unwrap();
+        assert_eq!(witness.to_string(), "81 + 40 * 27");
+
+        for solver in [Solver::Forward, Solver::Reverse] {
+            let witness = equations[4].solve(PART_2, solver).unwrap();
+            assert_eq!(witness.to_string(), "6 * 8 || 6 * 15");
+
+            assert!(equations[2].solve(PART_2, solver).is_none());
+        }
+    }
+
+    #[test]
+    fn no_pruning() {
+        let equation = &parse_equations("2: 10 4 3\n")[0];
+
+        assert!(equation.solve(PART_2, Solver::Forward).is_none());
+        assert_eq!(
+            equation
+                .solve(&[&Sub, &Div], Solver::Forward)
+                .unwrap()
+                .to_string(),
+            "10 - 4 / 3"
+        );
+    }
+
+    #[test]
+    fn long_and_large() {
+        // Every combination of twos is even, forwards that means trying all 3^39 of them
+        let terms = vec!["2"; 40].join(" ");
+        let equation = &parse_equations(&format!("{}: {}\n", (1_i128 << 40) + 1, terms))[0];
+        assert!(equation.solve(PART_2, Solver::Reverse).is_none());
+
+        // Too big for 64 bits at every step, without panicking either way
+        let equations =
+            parse_equations("9999999999999999999999999999: 9999999999999 9999999999999 99\n");
+        for solver in [Solver::Forward, Solver::Reverse] {
+            let witness = equations[0].solve(PART_2, solver).unwrap();
+            assert_eq!(witness.to_string(), "9999999999999 || 9999999999999 || 99");
+        }
+    }
+
+    #[test]
+    fn analyse() {
+        let equations = parse_equations(SAMPLE);
+
+        for solver in [Solver::Forward, Solver::Reverse] {
+            let analysis = equations
+                .iter()
+                .map(|e| e.analyse(PART_2, solver))
+                .map(|a| (a.solutions, a.verdict(), format!("{:?}", a.required)))
+                .collect::<Vec<_>>();
+
+            assert_eq!(
+                analysis,
+                [
+                    (1, "unique", "[*]".to_string()),
+                    (2, "ambiguous", "[*, +]".to_string()),
+                    (0, "unsolvable", "[]".to_string()),
+                    (1, "unique", "[||]".to_string()),
+                    (1, "unique", "[*, ||]".to_string()),
+                    (0, "unsolvable", "[]".to_string()),
+                    (1, "unique", "[+, ||]".to_string()),
+                    (0, "unsolvable", "[]".to_string()),
+                    (1, "unique", "[*, +]".to_string()),
+                ]
+            );
+        }
+
+        // Multiplying and adding ones leaves the target alone either way
+        let ones = &parse_equations("5: 5 1 1\n")[0];
+        assert_eq!(ones.analyse(PART_2, Solver::Forward).solutions, 1);
+        assert_eq!(ones.analyse(&[&Mul, &Div], Solver::Forward).solutions, 4);
+    }
+
+    #[test]
+    fn zero_terms() {
+        let equations = parse_equations(
+            "0: 5 0
+0: 3 4 0
+12: 3 0 4
+7: 0 7
+",
+        );
+
+        for solver in [Solver::Forward, Solver::Reverse] {
+            assert_eq!(
+                equations[0].solve(PART_2, solver).unwrap().to_string(),
+                "5 * 0"
+            );
+
+            // Any way of combining 3 and 4, then times zero
+            let analysis = equations[1].analyse(PART_2, solver);
+            assert_eq!(analysis.solutions, 3);
+            assert_eq!(format!("{:?}", analysis.required), "[*]");
+
+            assert_eq!(
+                equations
+                    .iter()
+                    .map(|e| e.analyse(PART_2, solver).solutions)
+                    .collect::<Vec<_>>(),
+                [1, 3, 1, 2]
+            );
+        }
+    }
+}
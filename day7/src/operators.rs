@@ -0,0 +1,240 @@
+use std::fmt;
+
+// Wide enough that products and concatenations of real puzzle terms don't overflow
+pub type Value = i128;
+
+// Combines the running total with the next term, left to right with no precedence.
+// None when the result doesn't exist or doesn't fit.
+pub trait Operator {
+    fn symbol(&self) -> &'static str;
+    fn apply(&self, acc: Value, term: Value) -> Option<Value>;
+
+    // Never produces a smaller value for positive terms, lets the search give up early
+    fn grows(&self) -> bool {
+        false
+    }
+
+    // The only `acc` for which `apply(acc, term)` is `result`, used to search from the
+    // target backwards. Operators that can't be undone uniquely leave this as None.
+    fn invert(&self, _result: Value, _term: Value) -> Option<Value> {
+        None
+    }
+
+    fn invertible(&self) -> bool {
+        false
+    }
+
+    // Whether `apply(acc, term)` comes out the same whatever `acc` is, as with times zero.
+    // Searching backwards there is nothing to undo, any value of the terms before will do.
+    fn absorbs(&self, _term: Value) -> bool {
+        false
+    }
+}
+
+pub struct Add;
+pub struct Mul;
+pub struct Concat;
+pub struct Sub;
+pub struct Div;
+pub struct Pow;
+
+impl Operator for Add {
+    fn symbol(&self) -> &'static str {
+        "+"
+    }
+
+    fn apply(&self, acc: Value, term: Value) -> Option<Value> {
+        acc.checked_add(term)
+    }
+
+    fn grows(&self) -> bool {
+        true
+    }
+
+    fn invert(&self, result: Value, term: Value) -> Option<Value> {
+        result.checked_sub(term)
+    }
+
+    fn invertible(&self) -> bool {
+        true
+    }
+}
+
+impl Operator for Mul {
+    fn symbol(&self) -> &'static str {
+        "*"
+    }
+
+    fn apply(&self, acc: Value, term: Value) -> Option<Value> {
+        acc.checked_mul(term)
+    }
+
+    fn grows(&self) -> bool {
+        true
+    }
+
+    fn invert(&self, result: Value, term: Value) -> Option<Value> {
+        // Anything times zero is zero, there is no single answer
+        if term == 0 || result % term != 0 {
+            return None;
+        }
+
+        result.checked_div(term)
+    }
+
+    fn invertible(&self) -> bool {
+        true
+    }
+
+    fn absorbs(&self, term: Value) -> bool {
+        term == 0
+    }
+}
+
+impl Operator for Concat {
+    fn symbol(&self) -> &'static str {
+        "||"
+    }
+
+    // Digits of `term` appended to `acc`, so 12 || 345 is 12345
+    fn apply(&self, acc: Value, term: Value) -> Option<Value> {
+        acc.checked_mul(Concat::shift(term)?)?.checked_add(term)
+    }
+
+    fn grows(&self) -> bool {
+        true
+    }
+
+    // Strips `term` off the end of `result`
+    fn invert(&self, result: Value, term: Value) -> Option<Value> {
+        let shift = Concat::shift(term)?;
+
+        (result >= 0 && result % shift == term).then_some(result / shift)
+    }
+
+    fn invertible(&self) -> bool {
+        true
+    }
+}
+
+impl Concat {
+    // The power of ten `acc` is scaled by to make room for the digits of `term`
+    fn shift(term: Value) -> Option<Value> {
+        if term < 0 {
+            return None;
+        }
+
+        let mut shift: Value = 10;
+        while shift <= term {
+            shift = shift.checked_mul(10)?;
+        }
+
+        Some(shift)
+    }
+}
+
+impl Operator for Sub {
+    fn symbol(&self) -> &'static str {
+        "-"
+    }
+
+    fn apply(&self, acc: Value, term: Value) -> Option<Value> {
+        acc.checked_sub(term)
+    }
+
+    fn invert(&self, result: Value, term: Value) -> Option<Value> {
+        result.checked_add(term)
+    }
+
+    fn invertible(&self) -> bool {
+        true
+    }
+}
+
+impl Operator for Div {
+    fn symbol(&self) -> &'static str {
+        "/"
+    }
+
+    // Integer division rounding towards zero
+    fn apply(&self, acc: Value, term: Value) -> Option<Value> {
+        acc.checked_div(term)
+    }
+}
+
+impl Operator for Pow {
+    fn symbol(&self) -> &'static str {
+        "^"
+    }
+
+    fn apply(&self, acc: Value, term: Value) -> Option<Value> {
+        acc.checked_pow(u32::try_from(term).ok()?)
+    }
+
+    fn absorbs(&self, term: Value) -> bool {
+        term == 0
+    }
+}
+
+impl fmt::Debug for dyn Operator + '_ {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
+
+pub const PART_2: &[&dyn Operator] = &[&Mul, &Add, &Concat];
+pub const ALL: &[&dyn Operator] = &[&Mul, &Add, &Concat, &Sub, &Div, &Pow];
+
+// Comma separated symbols, for example "*,+,||"
+pub fn parse_set(symbols: &str) -> Result<Vec<&'static dyn Operator>, String> {
+    symbols
+        .split(',')
+        .map(|symbol| {
+            ALL.iter()
+                .find(|op| op.symbol() == symbol.trim())
+                .copied()
+                .ok_or_else(|| format!("Unknown operator {:?}", symbol))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply() {
+        assert_eq!(Concat.apply(12, 345), Some(12345));
+        assert_eq!(Concat.apply(15, 0), Some(150));
+        assert_eq!(Concat.apply(Value::MAX / 10, 10), None);
+        assert_eq!(Div.apply(7, 2), Some(3));
+        assert_eq!(Div.apply(7, 0), None);
+        assert_eq!(Pow.apply(2, 10), Some(1024));
+        assert_eq!(Pow.apply(2, -1), None);
+    }
+
+    #[test]
+    fn invert() {
+        for op in ALL.iter().filter(|op| op.invertible()) {
+            for (acc, term) in [(12, 345), (0, 7), (15, 10), (6, 8)] {
+                let result = op.apply(acc, term).unwrap();
+                assert_eq!(op.invert(result, term), Some(acc), "{:?}", op);
+            }
+        }
+
+        assert_eq!(Concat.invert(12345, 45), Some(123));
+        assert_eq!(Concat.invert(12345, 44), None);
+        assert_eq!(Mul.invert(10, 3), None);
+        assert_eq!(Mul.invert(0, 0), None);
+        assert!(Mul.absorbs(0) && !Mul.absorbs(1) && !Add.absorbs(0));
+        assert!(!Div.invertible());
+    }
+
+    #[test]
+    fn parse() {
+        let set = parse_set("*, ||,^").unwrap();
+
+        assert_eq!(format!("{:?}", set), "[*, ||, ^]");
+        assert!(parse_set("*,%").is_err());
+    }
+}
@@ -3,137 +3,114 @@ use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::prelude::*;
 
+mod render;
+mod rules;
+use render::*;
+use rules::*;
+
 const INPUT: &str = "input.txt";
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Resonance {
+    // Repeats the full antenna spacing, as the puzzle counts it
+    Stepped,
+    // Every grid point on the line through both antennas, the spacing reduced by its gcd
+    Lattice,
+}
+
+fn gcd(a: isize, b: isize) -> isize {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
 struct TowerMap {
     towers: HashMap<char, Vec<(usize, usize)>>,
+    // Sizes, not the largest index
     rows: usize,
     cols: usize,
 }
 
+// One antinode and the antenna pair that produced it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Attribution {
+    antinode: (usize, usize),
+    frequency: char,
+    pair: ((usize, usize), (usize, usize)),
+}
+
 impl TowerMap {
-    fn combinations(list: &Vec<(usize, usize)>) -> Vec<((usize, usize), (usize, usize))> {
-        let mut combos: Vec<((usize, usize), (usize, usize))> = Vec::new();
+    fn new(contents: &str) -> TowerMap {
+        let mut towers: HashMap<char, Vec<(usize, usize)>> = HashMap::new();
+        let mut rows: usize = 0;
+        let mut cols: usize = 0;
 
-        for i in 0..list.len() {
-            for j in 0 + i + 1..list.len() {
-                combos.push((list[i], list[j]));
+        for (row, row_text) in contents.split("\n").filter(|r| !r.is_empty()).enumerate() {
+            for (col, ch) in row_text.chars().enumerate() {
+                if ch != '.' {
+                    towers.entry(ch).or_default().push((row, col));
+                }
             }
+
+            cols = max(cols, row_text.chars().count());
+            rows = row + 1;
         }
 
-        combos
+        TowerMap { towers, rows, cols }
     }
 
-    fn antinode_pair(
-        &self,
-        p1: (usize, usize),
-        p2: (usize, usize),
-        set: &mut HashSet<(usize, usize)>,
-    ) {
-        let p1: (isize, isize) = (
-            isize::try_from(p1.0).unwrap(),
-            isize::try_from(p1.1).unwrap(),
-        );
-        let p2: (isize, isize) = (
-            isize::try_from(p2.0).unwrap(),
-            isize::try_from(p2.1).unwrap(),
-        );
-
-        let dr: isize = p2.0 - p1.0;
-        let dc: isize = p2.1 - p1.1;
-
-        let an1: (isize, isize) = (p2.0 + dr, p2.1 + dc);
-        let an2: (isize, isize) = (p1.0 - dr, p1.1 - dc);
-
-        let an1 = (usize::try_from(an1.0), usize::try_from(an1.1));
-        let an2 = (usize::try_from(an2.0), usize::try_from(an2.1));
-
-        if let (Ok(r), Ok(c)) = an1 {
-            if r <= self.rows && c <= self.cols {
-                set.insert((r, c));
-            }
-        }
+    fn on_map(&self, (r, c): (isize, isize)) -> Option<(usize, usize)> {
+        let (Ok(r), Ok(c)) = (usize::try_from(r), usize::try_from(c)) else {
+            return None;
+        };
 
-        if let (Ok(r), Ok(c)) = an2 {
-            if r <= self.rows && c <= self.cols {
-                set.insert((r, c));
-            }
-        }
+        (r < self.rows && c < self.cols).then_some((r, c))
     }
 
-    fn all_antinodes(&self) -> HashSet<(usize, usize)> {
-        let mut set: HashSet<(usize, usize)> = HashSet::new();
-
-        for v in self.towers.values() {
-            let combos = TowerMap::combinations(v);
+    fn combinations(list: &[(usize, usize)]) -> Vec<((usize, usize), (usize, usize))> {
+        let mut combos: Vec<((usize, usize), (usize, usize))> = Vec::new();
 
-            for (p1, p2) in combos.iter() {
-                self.antinode_pair(*p1, *p2, &mut set);
+        for i in 0..list.len() {
+            for j in i + 1..list.len() {
+                combos.push((list[i], list[j]));
             }
         }
 
-        set
+        combos
     }
 
-    fn antinode_ray(
-        &self,
-        p1: (usize, usize),
-        p2: (usize, usize),
-        set: &mut HashSet<(usize, usize)>,
-        loop_limit: usize
-    ) {
-        set.insert(p2);
-
-        let p1: (isize, isize) = (
-            isize::try_from(p1.0).unwrap(),
-            isize::try_from(p1.1).unwrap(),
-        );
-        let p2: (isize, isize) = (
-            isize::try_from(p2.0).unwrap(),
-            isize::try_from(p2.1).unwrap(),
-        );
-
-        let dr: isize = p2.0 - p1.0;
-        let dc: isize = p2.1 - p1.1;
-
-        let mut last_node: (isize, isize) = p2;
-        let mut iterations: usize = 0;
-
-        loop {
-            if iterations >= loop_limit { break; }
-
-            let an: (isize, isize) = (last_node.0 + dr, last_node.1 + dc);
-
-            let an_usize = (usize::try_from(an.0), usize::try_from(an.1));
-
-            if let (Ok(r), Ok(c)) = an_usize {
-                if r <= self.rows && c <= self.cols {
-                    set.insert((r, c));
-                    last_node = an;
-                } else {
-                    break;
-                }
-            } else {
-                break;
-            }
-
-            iterations += 1;
-        }
+    // Antinodes of every pair of same frequency antennas, for frequencies passing `filter`
+    fn all_antinodes(&self, rule: &Rule, filter: impl Fn(char) -> bool) -> HashSet<(usize, usize)> {
+        self.attributions(rule, filter)
+            .iter()
+            .map(|a| a.antinode)
+            .collect()
     }
 
-    fn resonant_antinodes(&self) -> HashSet<(usize, usize)> {
-        let mut set: HashSet<(usize, usize)> = HashSet::new();
+    // Every (antinode, pair) combination, an antinode made by several pairs appears for
+    // each of them. Sorted by antinode.
+    fn attributions(&self, rule: &Rule, filter: impl Fn(char) -> bool) -> Vec<Attribution> {
+        let mut attributions: Vec<Attribution> = Vec::new();
 
-        for v in self.towers.values() {
+        for (&frequency, v) in self.towers.iter().filter(|(&f, _)| filter(f)) {
             let combos = TowerMap::combinations(v);
 
-            for (p1, p2) in combos.iter() {
-                self.antinode_ray(*p1, *p2, &mut set, 1000);
-                self.antinode_ray(*p2, *p1, &mut set, 1000);
+            for &pair in combos.iter() {
+                for antinode in rule.antinodes(self, pair.0, pair.1) {
+                    attributions.push(Attribution {
+                        antinode,
+                        frequency,
+                        pair,
+                    });
+                }
             }
         }
 
-        set
+        attributions.sort();
+        attributions.dedup();
+        attributions
     }
 }
 
@@ -144,37 +121,106 @@ fn main() {
         file.read_to_string(&mut contents)
             .expect("Failed to read contents of INPUT file");
 
-        let mut towers: HashMap<char, Vec<(usize, usize)>> = HashMap::new();
-        let mut rows: usize = 0;
-        let mut cols: usize = 0;
+        TowerMap::new(&contents)
+    };
 
-        for (row, row_text) in contents.split("\n").filter(|r| r.len() > 0).enumerate() {
-            for (col, ch) in row_text.chars().enumerate() {
-                if ch != '.' {
-                    if let Some(v) = towers.get_mut(&ch) {
-                        v.push((row, col));
-                    } else {
-                        let mut v: Vec<(usize, usize)> = Vec::new();
-                        v.push((row, col));
-                        towers.insert(ch, v);
-                    }
-                }
+    let args = std::env::args().collect::<Vec<_>>();
+    let value = |flag: &str| {
+        args.iter()
+            .position(|a| a == flag)
+            .map(|i| args.get(i + 1).map(String::as_str).unwrap_or_default())
+    };
 
-                cols = max(cols, col);
-            }
+    // Only the frequencies listed, all of them by default
+    let frequencies: Option<Vec<char>> = value("--freq").map(|f| f.chars().collect());
+    let filter = |f: char| frequencies.as_ref().is_none_or(|list| list.contains(&f));
 
-            rows = max(rows, row);
-        }
+    let rule: Option<Rule> = value("--rule").map(|r| r.parse().unwrap());
 
-        TowerMap {
-            towers: towers,
-            rows: rows,
-            cols: cols,
-        }
-    };
+    if args.iter().any(|a| a == "--render") {
+        let antinodes = towers.all_antinodes(&rule.unwrap_or(Rule::PART_1), filter);
+        print!("{}", render(&towers, &antinodes));
+        return;
+    }
+
+    if args.iter().any(|a| a == "--report") {
+        let attributions = towers.attributions(&rule.unwrap_or(Rule::PART_1), filter);
+        print!("{}", report(&attributions));
+        return;
+    }
 
-    let antinodes = towers.all_antinodes();
+    if let Some(rule) = rule {
+        let antinodes = towers.all_antinodes(&rule, filter);
+        println!("{:?} - Antinodes: {:?}", rule, antinodes.len());
+        return;
+    }
+
+    let antinodes = towers.all_antinodes(&Rule::PART_1, filter);
     println!("Part 1 - Antinodes: {:?}", antinodes.len());
-    let resonant_antinodes = towers.resonant_antinodes();
+    let resonant_antinodes = towers.all_antinodes(&Rule::PART_2, filter);
     println!("Part 2 - Antinodes: {:?}", resonant_antinodes.len());
+    let lattice = Rule::Harmonics {
+        resonance: Resonance::Lattice,
+        max_order: None,
+    };
+    let lattice_antinodes = towers.all_antinodes(&lattice, filter);
+    println!("Part 2 - Lattice Antinodes: {:?}", lattice_antinodes.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "............
+........0...
+.....0......
+.......0....
+....0.......
+......A.....
+............
+............
+........A...
+.........A..
+............
+............
+";
+
+    #[test]
+    fn sample() {
+        let towers = TowerMap::new(SAMPLE);
+        let lattice = Rule::Harmonics {
+            resonance: Resonance::Lattice,
+            max_order: None,
+        };
+
+        assert_eq!(towers.all_antinodes(&Rule::PART_1, |_| true).len(), 14);
+        assert_eq!(towers.all_antinodes(&Rule::PART_2, |_| true).len(), 34);
+        // No pair in the sample has a common factor in its spacing
+        assert_eq!(towers.all_antinodes(&lattice, |_| true).len(), 34);
+    }
+
+    #[test]
+    fn frequency_filter() {
+        let towers = TowerMap::new(SAMPLE);
+
+        assert_eq!(towers.all_antinodes(&Rule::PART_1, |f| f == 'A').len(), 5);
+        assert_eq!(towers.all_antinodes(&Rule::PART_1, |f| f == 'z').len(), 0);
+    }
+
+    #[test]
+    fn lattice() {
+        let towers = TowerMap::new("a.....\n......\n....a.\n......\n");
+
+        let mut stepped = Vec::from_iter(towers.all_antinodes(&Rule::PART_2, |_| true));
+        stepped.sort();
+        assert_eq!(stepped, [(0, 0), (2, 4)]);
+
+        let rule = Rule::Harmonics {
+            resonance: Resonance::Lattice,
+            max_order: None,
+        };
+        let mut lattice = Vec::from_iter(towers.all_antinodes(&rule, |_| true));
+        lattice.sort();
+        assert_eq!(lattice, [(0, 0), (1, 2), (2, 4)]);
+    }
 }
@@ -0,0 +1,119 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{Attribution, TowerMap};
+
+const EMPTY: char = '.';
+const ANTINODE: char = '#';
+
+// The map as the puzzle draws it, antennas hide any antinode on the same spot
+pub fn render(map: &TowerMap, antinodes: &HashSet<(usize, usize)>) -> String {
+    let mut grid: Vec<Vec<char>> = vec![vec![EMPTY; map.cols]; map.rows];
+
+    for &(row, col) in antinodes {
+        grid[row][col] = ANTINODE;
+    }
+
+    for (&frequency, towers) in map.towers.iter() {
+        for &(row, col) in towers {
+            grid[row][col] = frequency;
+        }
+    }
+
+    grid.iter()
+        .map(|row| row.iter().collect::<String>() + "\n")
+        .collect()
+}
+
+// One line per antinode and pair, then how many distinct antinodes each frequency made
+pub fn report(attributions: &[Attribution]) -> String {
+    let mut output = String::new();
+    let mut per_frequency: BTreeMap<char, HashSet<(usize, usize)>> = BTreeMap::new();
+
+    for a in attributions {
+        output += &format!(
+            "{:?} {} {:?} {:?}\n",
+            a.antinode, a.frequency, a.pair.0, a.pair.1
+        );
+        per_frequency
+            .entry(a.frequency)
+            .or_default()
+            .insert(a.antinode);
+    }
+
+    output.push('\n');
+    for (frequency, antinodes) in per_frequency.iter() {
+        output += &format!("{} - Antinodes: {}\n", frequency, antinodes.len());
+    }
+
+    let distinct = attributions
+        .iter()
+        .map(|a| a.antinode)
+        .collect::<HashSet<_>>();
+    output += &format!("Total - Antinodes: {}\n", distinct.len());
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Rule;
+
+    const SAMPLE: &str = "............
+........0...
+.....0......
+.......0....
+....0.......
+......A.....
+............
+............
+........A...
+.........A..
+............
+............
+";
+
+    const SAMPLE_RENDERED: &str = "......#....#
+...#....0...
+....#0....#.
+..#....0....
+....0....#..
+.#....A.....
+...#........
+#......#....
+........A...
+.........A..
+..........#.
+..........#.
+";
+
+    #[test]
+    fn sample() {
+        let map = TowerMap::new(SAMPLE);
+        let antinodes = map.all_antinodes(&Rule::PART_1, |_| true);
+
+        assert_eq!(render(&map, &antinodes), SAMPLE_RENDERED);
+    }
+
+    #[test]
+    fn attribution() {
+        let map = TowerMap::new("a..a..\n");
+        let attributions = map.attributions(&Rule::PART_2, |_| true);
+
+        assert_eq!(
+            report(&attributions),
+            "(0, 0) a (0, 0) (0, 3)
+(0, 3) a (0, 0) (0, 3)
+
+a - Antinodes: 2
+Total - Antinodes: 2
+"
+        );
+
+        // The last column really is the last one, nothing is drawn past it
+        assert_eq!(
+            render(&map, &map.all_antinodes(&Rule::PART_1, |_| true)),
+            "a..a..\n"
+        );
+    }
+}
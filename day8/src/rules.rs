@@ -0,0 +1,153 @@
+use crate::{gcd, Resonance, TowerMap};
+
+type Point = (usize, usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    // In line with both antennas and k times as far from one as from the other, on
+    // either side of the pair. Ratio(2) is the puzzle's first rule.
+    Ratio(usize),
+    // Halfway between the antennas, when that is a grid point
+    Midpoint,
+    // Repeats of the spacing out from each antenna, the antennas themselves are order 0.
+    // No maximum order walks to the edge of the map, which is the puzzle's second rule.
+    Harmonics {
+        resonance: Resonance,
+        max_order: Option<usize>,
+    },
+}
+
+impl Rule {
+    pub const PART_1: Rule = Rule::Ratio(2);
+    pub const PART_2: Rule = Rule::Harmonics {
+        resonance: Resonance::Stepped,
+        max_order: None,
+    };
+
+    pub fn antinodes(&self, map: &TowerMap, p1: Point, p2: Point) -> Vec<Point> {
+        let a: (isize, isize) = (p1.0 as isize, p1.1 as isize);
+        let b: (isize, isize) = (p2.0 as isize, p2.1 as isize);
+        let (dr, dc) = (b.0 - a.0, b.1 - a.1);
+
+        let mut points: Vec<(isize, isize)> = Vec::new();
+
+        match *self {
+            Rule::Ratio(k) => {
+                // k * |p - b| = |p - a| puts p a (k - 1)th of the spacing beyond b
+                let k = k as isize;
+                if k > 1 && dr % (k - 1) == 0 && dc % (k - 1) == 0 {
+                    let (sr, sc) = (dr / (k - 1), dc / (k - 1));
+                    points.push((b.0 + sr, b.1 + sc));
+                    points.push((a.0 - sr, a.1 - sc));
+                }
+            }
+            Rule::Midpoint => {
+                if dr % 2 == 0 && dc % 2 == 0 {
+                    points.push((a.0 + dr / 2, a.1 + dc / 2));
+                }
+            }
+            Rule::Harmonics {
+                resonance,
+                max_order,
+            } => {
+                let divisor = match resonance {
+                    Resonance::Stepped => 1,
+                    Resonance::Lattice => gcd(dr, dc),
+                };
+                let (sr, sc) = (dr / divisor, dc / divisor);
+
+                // The antennas and any lattice points between them
+                for i in 0..=divisor {
+                    points.push((a.0 + sr * i, a.1 + sc * i));
+                }
+
+                for (start, (sr, sc)) in [(b, (sr, sc)), (a, (-sr, -sc))] {
+                    let mut order = 1;
+                    let mut node = (start.0 + sr, start.1 + sc);
+
+                    while max_order.is_none_or(|max| order <= max) && map.on_map(node).is_some() {
+                        points.push(node);
+                        order += 1;
+                        node = (node.0 + sr, node.1 + sc);
+                    }
+                }
+            }
+        }
+
+        points.into_iter().filter_map(|p| map.on_map(p)).collect()
+    }
+}
+
+// ratio:K, midpoint, harmonics, harmonics:N, lattice or lattice:N
+impl std::str::FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, arg) = match s.split_once(':') {
+            Some((name, arg)) => (name, Some(arg)),
+            None => (s, None),
+        };
+
+        let arg = arg
+            .map(|a| {
+                a.parse::<usize>()
+                    .map_err(|_| format!("Bad rule argument {:?}", a))
+            })
+            .transpose()?;
+
+        match (name, arg) {
+            ("ratio", Some(k)) if k > 1 => Ok(Rule::Ratio(k)),
+            ("midpoint", None) => Ok(Rule::Midpoint),
+            ("harmonics", max_order) => Ok(Rule::Harmonics {
+                resonance: Resonance::Stepped,
+                max_order,
+            }),
+            ("lattice", max_order) => Ok(Rule::Harmonics {
+                resonance: Resonance::Lattice,
+                max_order,
+            }),
+            _ => Err(format!("Unknown rule {:?}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns(row: &str, rule: &str) -> Vec<usize> {
+        let map = TowerMap::new(row);
+        let rule: Rule = rule.parse().unwrap();
+
+        let mut antinodes = map
+            .all_antinodes(&rule, |_| true)
+            .into_iter()
+            .map(|(_, col)| col)
+            .collect::<Vec<_>>();
+        antinodes.sort();
+        antinodes
+    }
+
+    #[test]
+    fn rules() {
+        let row = "..a..a.........\n";
+
+        assert_eq!(columns(row, "ratio:2"), [8]);
+        assert_eq!(columns(row, "ratio:3"), []);
+        assert_eq!(columns(row, "ratio:4"), [1, 6]);
+        assert_eq!(columns(row, "midpoint"), []);
+        assert_eq!(columns("..a...a\n", "midpoint"), [4]);
+        assert_eq!(columns(row, "harmonics:1"), [2, 5, 8]);
+        assert_eq!(columns(row, "harmonics"), [2, 5, 8, 11, 14]);
+        assert_eq!(columns(row, "lattice:1"), [1, 2, 3, 4, 5, 6]);
+        assert_eq!(columns(row, "lattice").len(), 15);
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("ratio:2".parse(), Ok(Rule::PART_1));
+        assert_eq!("harmonics".parse(), Ok(Rule::PART_2));
+        assert!("ratio:1".parse::<Rule>().is_err());
+        assert!("midpoint:3".parse::<Rule>().is_err());
+    }
+}
@@ -0,0 +1,405 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::{Block, ID};
+
+// A run of blocks belonging to one file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub id: ID,
+    pub start: usize,
+    pub len: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gap {
+    pub start: usize,
+    pub len: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fit {
+    // Leftmost gap the file fits in
+    First,
+    // Shortest gap it fits in, then leftmost
+    Best,
+    // Longest gap, then leftmost
+    Worst,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiskError {
+    // Position in the disk map and the character that isn't a digit
+    BadDigit(usize, char),
+    // More files than ID can number
+    TooManyFiles,
+    // The dense format numbers files by position, one run each, starting at block 0
+    OutOfOrder { expected: ID, found: ID },
+    // A file starting at `start`, before the file ahead of it in id order ends at `end`
+    Overlap { start: usize, end: usize },
+    LeadingGap(usize),
+    // A single digit can't describe more than 9 blocks
+    RunTooLong { start: usize, len: usize },
+}
+
+// Files in id order, a file split up block by block has a span for each piece.
+// Anything not covered by a file is free.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disk {
+    pub files: Vec<Span>,
+    pub len: usize,
+}
+
+impl Disk {
+    pub fn parse(contents: &str) -> Result<Disk, DiskError> {
+        let mut files: Vec<Span> = Vec::new();
+        let mut start: usize = 0;
+        let digits = contents
+            .chars()
+            .enumerate()
+            .filter(|(_, ch)| !ch.is_whitespace());
+
+        for (i, (pos, val)) in digits.enumerate() {
+            let len = val.to_digit(10).ok_or(DiskError::BadDigit(pos, val))? as usize;
+
+            if i % 2 == 0 {
+                files.push(Span {
+                    id: ID::try_from(files.len()).map_err(|_| DiskError::TooManyFiles)?,
+                    start,
+                    len,
+                });
+            }
+
+            start += len;
+        }
+
+        Ok(Disk { files, len: start })
+    }
+
+    // The dense disk map, exactly as `parse` reads it. Only layouts with one run per file,
+    // in id order, can be written, see `renumbered` for anything else.
+    pub fn encode(&self) -> Result<String, DiskError> {
+        let digit = |start: usize, len: usize| match u32::try_from(len) {
+            Ok(len) if len <= 9 => Ok(char::from_digit(len, 10).unwrap()),
+            _ => Err(DiskError::RunTooLong { start, len }),
+        };
+
+        let mut output = String::new();
+        let mut end: usize = 0;
+
+        for (i, file) in self.files.iter().enumerate() {
+            let expected = ID::try_from(i).map_err(|_| DiskError::TooManyFiles)?;
+
+            if file.id != expected {
+                return Err(DiskError::OutOfOrder {
+                    expected,
+                    found: file.id,
+                });
+            }
+            if file.start < end {
+                return Err(DiskError::Overlap {
+                    start: file.start,
+                    end,
+                });
+            }
+
+            if i == 0 && file.start > 0 {
+                return Err(DiskError::LeadingGap(file.start));
+            }
+            if i > 0 {
+                output.push(digit(end, file.start - end)?);
+            }
+
+            output.push(digit(file.start, file.len)?);
+            end = file.start + file.len;
+        }
+
+        if self.len > end {
+            output.push(digit(end, self.len - end)?);
+        }
+
+        Ok(output)
+    }
+
+    // The same blocks used and free, in a shape `encode` always accepts. Files are
+    // numbered by position, runs longer than a digit are cut into several files, and long
+    // gaps are broken up with empty files. Which file a block belonged to is lost.
+    pub fn renumbered(&self) -> Result<Disk, DiskError> {
+        const RUN: usize = 9;
+
+        fn push(files: &mut Vec<Span>, start: usize, len: usize) -> Result<(), DiskError> {
+            let id = ID::try_from(files.len()).map_err(|_| DiskError::TooManyFiles)?;
+            files.push(Span { id, start, len });
+            Ok(())
+        }
+
+        let mut pieces = self.files.iter().filter(|f| f.len > 0).collect::<Vec<_>>();
+        pieces.sort_by_key(|f| f.start);
+
+        let mut files: Vec<Span> = Vec::new();
+        let mut end: usize = 0;
+
+        for piece in pieces {
+            if files.is_empty() && piece.start > 0 {
+                push(&mut files, 0, 0)?;
+            }
+            while piece.start - end > RUN {
+                end += RUN;
+                push(&mut files, end, 0)?;
+            }
+
+            for offset in (0..piece.len).step_by(RUN) {
+                push(
+                    &mut files,
+                    piece.start + offset,
+                    RUN.min(piece.len - offset),
+                )?;
+            }
+            end = piece.start + piece.len;
+        }
+
+        if files.is_empty() && self.len > 0 {
+            push(&mut files, 0, 0)?;
+        }
+        while self.len - end > RUN {
+            end += RUN;
+            push(&mut files, end, 0)?;
+        }
+
+        Ok(Disk {
+            files,
+            len: self.len,
+        })
+    }
+
+    pub fn from_blocks(blocks: &[Block]) -> Disk {
+        let mut files: Vec<Span> = Vec::new();
+
+        for (i, &block) in blocks.iter().enumerate() {
+            let Some(id) = block else {
+                continue;
+            };
+
+            match files.last_mut() {
+                Some(span) if span.id == id && span.start + span.len == i => span.len += 1,
+                _ => files.push(Span {
+                    id,
+                    start: i,
+                    len: 1,
+                }),
+            }
+        }
+
+        files.sort_by_key(|f| (f.id, f.start));
+
+        Disk {
+            files,
+            len: blocks.len(),
+        }
+    }
+
+    pub fn blocks(&self) -> Vec<Block> {
+        let mut blocks: Vec<Block> = vec![None; self.len];
+
+        for file in self.files.iter() {
+            blocks[file.start..file.start + file.len].fill(Some(file.id));
+        }
+
+        blocks
+    }
+
+    // Free runs between files, in disk order
+    pub fn gaps(&self) -> Vec<Gap> {
+        let mut files = self.files.iter().filter(|f| f.len > 0).collect::<Vec<_>>();
+        files.sort_by_key(|f| f.start);
+
+        let mut gaps: Vec<Gap> = Vec::new();
+        let mut end: usize = 0;
+
+        for file in files.iter().map(|f| (f.start, f.start + f.len)) {
+            if file.0 > end {
+                gaps.push(Gap {
+                    start: end,
+                    len: file.0 - end,
+                });
+            }
+            end = file.1;
+        }
+
+        if self.len > end {
+            gaps.push(Gap {
+                start: end,
+                len: self.len - end,
+            });
+        }
+
+        gaps
+    }
+
+    // Moves each file, highest id first, into a gap further left that fits it. Gaps
+    // are kept in one min-heap of starts per gap length, which the puzzle's digits keep
+    // short, so finding a gap is a scan over lengths. Expects one span per file.
+    pub fn compact_files(&mut self, fit: Fit) {
+        let gaps = self.gaps();
+        let longest = gaps.iter().map(|g| g.len).max().unwrap_or_default();
+        let mut heaps: Vec<BinaryHeap<Reverse<usize>>> = vec![BinaryHeap::new(); longest + 1];
+
+        for gap in gaps {
+            heaps[gap.len].push(Reverse(gap.start));
+        }
+
+        for file in self.files.iter_mut().rev() {
+            if file.len == 0 {
+                continue;
+            }
+
+            // The leftmost gap of each length, if it is left of the file. The space the
+            // file leaves behind is right of every file still to move, so is never needed.
+            let mut candidates = (file.len..heaps.len())
+                .filter_map(|len| heaps[len].peek().map(|&Reverse(start)| (start, len)))
+                .filter(|&(start, _)| start < file.start);
+
+            let chosen = match fit {
+                Fit::First => candidates.min(),
+                Fit::Best => candidates.next(),
+                Fit::Worst => candidates.next_back(),
+            };
+
+            let Some((start, len)) = chosen else {
+                continue;
+            };
+
+            heaps[len].pop();
+            if len > file.len {
+                heaps[len - file.len].push(Reverse(start + file.len));
+            }
+            file.start = start;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "2333133121414131402";
+
+    // Block by block first fit, the way the puzzle describes it
+    fn naive(blocks: &mut [Block], files: usize) {
+        for id in (0..files).rev().map(|id| Some(id as ID)) {
+            let Some(start) = blocks.iter().position(|&b| b == id) else {
+                continue;
+            };
+            let len = blocks[start..].iter().take_while(|&&b| b == id).count();
+
+            let gap = (0..start).find(|&i| blocks[i..i + len].iter().all(|b| b.is_none()));
+
+            if let Some(gap) = gap.filter(|&g| g + len <= start) {
+                blocks[gap..gap + len].fill(id);
+                blocks[start..start + len].fill(None);
+            }
+        }
+    }
+
+    // A dense disk map of `n` digits
+    fn digits(seed: u64, n: usize) -> String {
+        let mut state = seed;
+        let mut map = String::new();
+
+        for _ in 0..n {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            map.push(char::from(b'0' + (state >> 33) as u8 % 10));
+        }
+
+        map
+    }
+
+    #[test]
+    fn sample() {
+        let mut disk = Disk::parse(SAMPLE).unwrap();
+        assert_eq!(disk.blocks().len(), 42);
+        assert_eq!(disk.gaps().iter().map(|g| g.len).sum::<usize>(), 14);
+
+        disk.compact_files(Fit::First);
+
+        let blocks = disk.blocks();
+        assert_eq!(crate::checksum(&blocks), 2858);
+    }
+
+    #[test]
+    fn matches_naive() {
+        let mut disk = Disk::parse(&digits(9, 2000)).unwrap();
+        let mut blocks = disk.blocks();
+
+        disk.compact_files(Fit::First);
+        naive(&mut blocks, disk.files.len());
+
+        assert_eq!(disk.blocks(), blocks);
+        disk.files.retain(|f| f.len > 0);
+        assert_eq!(Disk::from_blocks(&blocks), disk);
+    }
+
+    #[test]
+    fn round_trip() {
+        for len in 0..200 {
+            let map = digits(48 + len as u64, len);
+            let disk = Disk::parse(&map).unwrap();
+            let encoded = disk.encode().unwrap();
+
+            // Nothing can follow a trailing empty gap, so it is left out
+            match map.strip_suffix('0').filter(|_| len % 2 == 0) {
+                Some(map) => assert_eq!(encoded, map),
+                None => assert_eq!(encoded, map),
+            }
+            assert_eq!(Disk::parse(&encoded).unwrap(), disk);
+        }
+
+        assert_eq!(Disk::parse(SAMPLE).unwrap().encode().unwrap(), SAMPLE);
+    }
+
+    #[test]
+    fn encode_layouts() {
+        let mut disk = Disk::parse(SAMPLE).unwrap();
+        disk.compact_files(Fit::First);
+
+        // File 2 moved into the gap ahead of file 1
+        assert_eq!(disk.encode(), Err(DiskError::Overlap { start: 4, end: 8 }));
+
+        let missing = Disk::from_blocks(&[Some(0), None, Some(2)]);
+        assert_eq!(
+            missing.encode(),
+            Err(DiskError::OutOfOrder {
+                expected: 1,
+                found: 2
+            })
+        );
+
+        // 00992111777.44.333....5555.6666.....8888..
+        let renumbered = disk.renumbered().unwrap();
+        let map = renumbered.encode().unwrap();
+        assert_eq!(map, "20201030312134414542");
+        assert_eq!(Disk::parse(&map).unwrap(), renumbered);
+
+        // Long runs either side of a file longer than a digit
+        let blocks = [vec![None; 12], vec![Some(7); 11], vec![None; 10]].concat();
+        let renumbered = Disk::from_blocks(&blocks).renumbered().unwrap();
+        let map = renumbered.encode().unwrap();
+        assert_eq!(map, "0903902901");
+        assert_eq!(Disk::parse(&map).unwrap().blocks(), renumbered.blocks());
+        assert_eq!(
+            renumbered
+                .blocks()
+                .iter()
+                .map(Option::is_some)
+                .collect::<Vec<_>>(),
+            blocks.iter().map(Option::is_some).collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            Disk::parse("12\n3x").unwrap_err(),
+            DiskError::BadDigit(4, 'x')
+        );
+    }
+}
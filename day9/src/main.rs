@@ -1,123 +1,52 @@
 use std::fs::File;
 use std::io::prelude::*;
 
+mod disk;
+mod strategy;
+use disk::*;
+use strategy::*;
+
 const INPUT: &str = "input.txt";
 
-type ID = u16;
+// Parsing reports an error rather than wrap if there are more files than this can number
+type ID = u32;
 type Block = Option<ID>;
 
-fn _part1_rearrange_disk(vec: &mut Vec<Block>) {
+fn part1_rearrange_disk(vec: &mut [Block]) {
+    if vec.is_empty() {
+        return;
+    }
+
     let mut left: usize = 0;
     let mut right: usize = vec.len() - 1;
 
     while left < right {
-        
         match (vec[left], vec[right]) {
-            (_, None) => { right -= 1; },
-            (Some(_), _) => { left += 1; },
+            (_, None) => {
+                right -= 1;
+            }
+            (Some(_), _) => {
+                left += 1;
+            }
             (None, Some(val)) => {
                 vec[left] = Some(val);
                 vec[right] = None;
 
                 left += 1;
                 right -= 1;
-            },
-        }
-    }
-}
-
-fn part2_rearrange_disk(vec: &mut Vec<Block>) {
-
-    fn find_contiguous_space(disk: &Vec<Block>, size: usize, right_max: usize) -> Option<(usize, usize)> {
-        let mut left: usize = 0;
-        let mut found: Option<(usize, usize)> = None;
-
-        while left < right_max {
-            if let None = disk[left] {
-                if let Some((l, _)) = found {
-                    found = Some((l, left))
-                } else {
-                    found = Some((left, left))
-                }
-
-                if let Some((l, r)) = found {
-                    if r - l + 1 >= size { break; }
-                }
-
-            } else {
-                found = None;
-            }
-
-            left += 1;
-        }
-
-        if left >= right_max {
-            found = None
-        }
-
-        found
-    }
-
-    fn get_current_block(disk: &Vec<Block>, start: usize) -> (usize, usize) {
-        let value = disk[start];
-        let mut left = start;
-        let mut right = start + 1;
-
-        for (i, v) in disk.iter().enumerate().skip(start) {
-            if *v == value {
-                right = i;
-            } else {
-                break;
-            }
-        }
-
-        for (i, v) in disk.iter().enumerate().rev().skip(disk.len() - start - 1) {
-            if *v == value {
-                left = i;
-            } else {
-                break;
             }
         }
-
-        (left, right)
-    }
-
-    fn copy_file(disk: &mut Vec<Block>, dest: (usize, usize), source: (usize, usize)) {
-        assert_eq!(dest.1 - dest.0 , source.1 - source.0, "{:?} {:?}", dest, source);
-
-        for i in 0..dest.1-dest.0+1 {
-            disk[dest.0+i] = disk[source.0+i];
-            disk[source.0+i] = None;
-        }
-    }
-
-    let mut current_block: (usize, usize);
-    let mut avail_space: Option<(usize, usize)>;
-    let mut i: usize = vec.len() - 1;
-
-    while i > 0 {
-        current_block = get_current_block(&vec, i);
-        avail_space = find_contiguous_space(&vec, current_block.1 - current_block.0 + 1, current_block.0);
-
-        if let (Some(_), Some(space)) = (vec[i], avail_space) {
-            copy_file(vec, space, current_block);
-        }
-
-        // Skip to next block
-        if current_block.0 <= 0 {
-            i = 0;
-        } else {
-            i = current_block.0 - 1;
-        }
     }
 }
 
-fn checksum(vec: &Vec<Block>) -> usize {
-    let mut acc: usize = 0;
+// Grows with the square of the disk length times the file count, so a map of a few
+// million digits already goes past u64
+fn checksum(vec: &[Block]) -> u128 {
+    let mut acc: u128 = 0;
 
     for (pos, e) in vec.iter().enumerate() {
         if let Some(v) = e {
-            acc += pos * usize::from(*v);
+            acc += pos as u128 * *v as u128;
         }
     }
 
@@ -125,41 +54,42 @@ fn checksum(vec: &Vec<Block>) -> usize {
 }
 
 fn main() {
-    let mut disk_map: Vec<Block> = {
+    let mut disk: Disk = {
         let mut file: File = File::open(INPUT).expect("Failed to open INPUT file");
         let mut contents: String = String::new();
         file.read_to_string(&mut contents)
             .expect("Failed to read contents of INPUT file");
 
-        fn push_n_times(vec: &mut Vec<Block>, repeats: ID, value: Block) {
-            for _ in 0..repeats {
-                vec.push(value);
-            }
-        }
-
-        let mut disk_map: Vec<Block> = Vec::new();
-        let mut file: bool = true;
-        let mut file_id: ID = 0;
+        Disk::parse(&contents).expect("Failed to parse disk map")
+    };
 
-        for val in contents.chars().filter(|ch| ch.is_numeric()) {
-            let num = val.to_string().parse::<ID>().expect("Failed to parse");
+    if std::env::args().any(|a| a == "--strategies") {
+        print!("{}", compare(&disk));
+        return;
+    }
 
-            if file {
-                push_n_times(&mut disk_map, num, Some(file_id));
-                file_id += 1;
-            } else {
-                push_n_times(&mut disk_map, num, None);
-            }
+    disk.compact_files(Fit::First);
 
-            file = !file;
-        }
+    // Files are renumbered by position, the dense format has nowhere to keep their ids
+    if std::env::args().any(|a| a == "--encode") {
+        let renumbered = disk.renumbered().expect("Failed to renumber disk");
+        println!("{}", renumbered.encode().expect("Failed to encode disk"));
+        return;
+    }
+    let disk_map: Vec<Block> = disk.blocks();
+    println!("{:?} {:?}", disk_map, checksum(&disk_map));
+}
 
-        disk_map
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    //part1_rearrange_disk(&mut disk_map);
-    //println!("{:?} {:?}", disk_map, checksum(&disk_map));
+    #[test]
+    fn large_checksum() {
+        let blocks: Vec<Block> = vec![Some(ID::MAX); 5_000_000];
+        let positions: u128 = 5_000_000 * 4_999_999 / 2;
 
-    part2_rearrange_disk(&mut disk_map);
-    println!("{:?} {:?}", disk_map, checksum(&disk_map));
+        assert_eq!(checksum(&blocks), positions * ID::MAX as u128);
+        assert!(checksum(&blocks) > u64::MAX as u128);
+    }
 }
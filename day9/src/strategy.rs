@@ -0,0 +1,172 @@
+use crate::disk::*;
+use crate::{checksum, part1_rearrange_disk, Block};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    // Fills gaps block by block from the end of the disk, splitting files up
+    Fragment,
+    FirstFit,
+    BestFit,
+    WorstFit,
+    // First fit, then every file slides left so no free space is left between them
+    Defragment,
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 5] = [
+        Strategy::Fragment,
+        Strategy::FirstFit,
+        Strategy::BestFit,
+        Strategy::WorstFit,
+        Strategy::Defragment,
+    ];
+
+    pub fn apply(&self, disk: &Disk) -> Disk {
+        let mut disk = disk.clone();
+
+        match self {
+            Strategy::Fragment => {
+                let mut blocks: Vec<Block> = disk.blocks();
+                part1_rearrange_disk(&mut blocks);
+                disk = Disk::from_blocks(&blocks);
+            }
+            Strategy::FirstFit => disk.compact_files(Fit::First),
+            Strategy::BestFit => disk.compact_files(Fit::Best),
+            Strategy::WorstFit => disk.compact_files(Fit::Worst),
+            Strategy::Defragment => {
+                disk.compact_files(Fit::First);
+
+                let mut order = (0..disk.files.len()).collect::<Vec<_>>();
+                order.sort_by_key(|&i| disk.files[i].start);
+
+                let mut start: usize = 0;
+                for i in order {
+                    disk.files[i].start = start;
+                    start += disk.files[i].len;
+                }
+            }
+        }
+
+        disk
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fragmentation {
+    // Free runs with file blocks after them
+    pub holes: usize,
+    // Spans beyond the first for every file
+    pub extra_pieces: usize,
+    // 1 - largest free run / all free space, 0 when the free space is in one piece
+    pub free_space: f64,
+}
+
+impl Fragmentation {
+    pub fn measure(disk: &Disk) -> Self {
+        let gaps = disk.gaps();
+        let total: usize = gaps.iter().map(|g| g.len).sum();
+        let largest: usize = gaps.iter().map(|g| g.len).max().unwrap_or_default();
+
+        let mut files = disk
+            .files
+            .iter()
+            .filter(|f| f.len > 0)
+            .map(|f| f.id)
+            .collect::<Vec<_>>();
+        let pieces = files.len();
+        files.dedup();
+
+        Fragmentation {
+            holes: gaps.iter().filter(|g| g.start + g.len < disk.len).count(),
+            extra_pieces: pieces - files.len(),
+            free_space: match total {
+                0 => 0.0,
+                _ => 1.0 - largest as f64 / total as f64,
+            },
+        }
+    }
+}
+
+// One line per strategy with its checksum and how fragmented it leaves the disk
+pub fn compare(disk: &Disk) -> String {
+    let mut output = format!(
+        "{:<12} {:>16} {:>8} {:>8} {:>6}\n",
+        "strategy", "checksum", "holes", "pieces", "free"
+    );
+
+    for strategy in Strategy::ALL {
+        let result = strategy.apply(disk);
+        let fragmentation = Fragmentation::measure(&result);
+
+        output += &format!(
+            "{:<12} {:>16} {:>8} {:>8} {:>6.3}\n",
+            format!("{:?}", strategy),
+            checksum(&result.blocks()),
+            fragmentation.holes,
+            fragmentation.extra_pieces,
+            fragmentation.free_space
+        );
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ID;
+
+    const SAMPLE: &str = "2333133121414131402";
+
+    #[test]
+    fn sample() {
+        let disk = Disk::parse(SAMPLE).unwrap();
+        let checksums = Strategy::ALL.map(|s| checksum(&s.apply(&disk).blocks()));
+
+        assert_eq!(checksums[0], 1928);
+        assert_eq!(checksums[1], 2858);
+    }
+
+    // Digits are file ids, dots are free
+    fn layout(text: &str) -> Vec<Block> {
+        text.chars()
+            .map(|ch| ch.to_digit(10).map(|id| id as ID))
+            .collect()
+    }
+
+    #[test]
+    fn fits() {
+        let results =
+            |map: &str| Strategy::ALL.map(|s| s.apply(&Disk::parse(map).unwrap()).blocks());
+
+        // 0...1..2, the last file fits best in the second gap
+        let [_, first, best, worst, _] = results("13121");
+        assert_eq!(first, layout("021....."));
+        assert_eq!(best, layout("01...2.."));
+        assert_eq!(worst, layout("021....."));
+
+        // 0.1...2, the second gap is the largest
+        let [_, first, best, worst, _] = results("11131");
+        assert_eq!(first, layout("021...."));
+        assert_eq!(best, layout("021...."));
+        assert_eq!(worst, layout("01.2..."));
+    }
+
+    #[test]
+    fn fragmentation() {
+        let disk = Disk::parse(SAMPLE).unwrap();
+
+        let fragmented = Fragmentation::measure(&Strategy::Fragment.apply(&disk));
+        assert_eq!(fragmented.holes, 0);
+        assert_eq!(fragmented.extra_pieces, 3);
+        assert_eq!(fragmented.free_space, 0.0);
+
+        let first_fit = Fragmentation::measure(&Strategy::FirstFit.apply(&disk));
+        assert_eq!(first_fit.holes, 5);
+        assert_eq!(first_fit.extra_pieces, 0);
+
+        let defragmented = Strategy::Defragment.apply(&disk);
+        assert_eq!(Fragmentation::measure(&defragmented).holes, 0);
+        assert_eq!(defragmented.gaps(), [Gap { start: 28, len: 14 }]);
+    }
+}